@@ -6,21 +6,125 @@
 use crate::BaseGame;
 use crate::network::{NetworkHandle, NetUpdate, StreamHandle};
 use crate::painter::TextConfig;
-use crate::players::{ClientPlayerManager, HostPlayerManager, PlayerManagerConfig};
+use crate::maps::{draw_map, MapConfig, MapUiConfig};
+use crate::hotseat::{HotSeatConfig, HotSeatManager, HotSeatManagerThinkResult, HotSeatUiConfig};
+use crate::powerups::{draw_pickups, PowerUpConfig, PowerUpUiConfig, PowerUps};
+use crate::practice::{PracticeConfig, PracticeManager, PracticeManagerThinkResult, PracticeUiConfig};
+use crate::placement::{clicked_start_point, draw_countdown, draw_start_points, PlacementUiConfig, StartPointMarker};
+use crate::players::{ClientPlayerManager, HostPlayerManager, PlayerManagerConfig, SavedPlayers};
+use crate::replay::{Replay, ReplayConfig, ReplayRecorder, ReplayUiConfig, ReplayViewer, ReplayViewerThinkResult};
+use crate::round::{draw_coins, draw_results, draw_timer, draw_zones, random_coin, GameMode, RoundConfig, RoundFrame, RoundResults,
+                   RoundState, RoundTracker, RoundUiConfig, Zone};
+use crate::series::{draw_standings, Series, SeriesConfig, SeriesMode, SeriesUiConfig};
+use crate::snake::SnakeUiConfig;
+use crate::shrink::{draw_boundary, ArenaRect, Boundary, BoundaryMsg, ShrinkConfig, ShrinkSchedule, ShrinkUiConfig};
+use crate::traits::{handle_ping, NetMsg, NewClientReplyMsg, BUILD_ID};
 use crate::utils::log;
 use crate::widgets::{Button, ButtonConfig, ButtonThinkResult};
 
 use engine_p::interpolable::Pos2d;
 use serde::{Serialize, Deserialize};
+use serde_with::serde_as;
 
 use std::collections::HashMap;
 
 // Network messages
 
 // .. sent from host to clients
+
+// Sent to all clients when coins are taken, zones move or scores change during a round
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PlaceCoins {
+    coins: Vec<Pos2d>,
+    #[serde(default)]
+    zones: Vec<Zone>,
+    #[serde_as(as = "Vec<(_, _)>")]
+    scores: HashMap<u32, u32>, // by the player's join order
+    #[serde(default)]
+    #[serde_as(as = "Vec<(_, _)>")]
+    collected: HashMap<u32, u32>, // coins collected this round, by the player's join order
+    #[serde(default)]
+    round: Box<RoundState>, // boxed, as most updates are for coins and scores
+}
+
+// Everything about the match beyond the players, sent to a client when it joins.  The
+// players, and their snakes' points, come over the players_stream.  Updates sent after
+// it carry a higher 'version', so the client can tell which ones it already has.
+// Maps go out as lists of pairs, since JS Maps can't be sent or saved as JSON.
+#[serde_as]
 #[derive(Debug, Deserialize, Serialize)]
-struct PlaceCoins {
+pub struct SnapshotMsg {
+    version: u32,
+    phase: GameState,
+    round_time_left: Option<f64>, // seconds, since our clocks differ
     coins: Vec<Pos2d>,
+    #[serde(default)]
+    zones: Vec<Zone>,
+    #[serde_as(as = "Vec<(_, _)>")]
+    scores: HashMap<u32, u32>, // by the player's join order
+    #[serde(default)]
+    results: Option<RoundResults>, // once the round is over
+    #[serde(default)]
+    series: Option<Series>,
+    #[serde(default)]
+    boundary: Option<BoundaryMsg>, // while the arena shrinks
+    #[serde(default)]
+    map: Option<MapConfig>, // of the current round
+    #[serde(default)]
+    power_ups: PowerUps,
+    #[serde(default)]
+    #[serde_as(as = "Vec<(_, _)>")]
+    collected: HashMap<u32, u32>, // coins collected this round, by the player's join order
+    #[serde(default)]
+    round: RoundState,
+}
+
+// An update to part of the match state, with the 'version' the state has once it's
+// applied, so a client can drop those its snapshot already has
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Versioned<T> {
+    version: u32,
+    update: T,
+}
+
+// Sent to all clients when the match moves on to another phase
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PhaseMsg {
+    version: u32,
+    phase: GameState,
+    time_left: Option<f64>, // seconds until the phase ends, if it's timed
+}
+
+// Everything the host knows about the game, to save it while debugging and restore it later
+#[derive(Deserialize, Serialize)]
+pub struct SavedGame {
+    build_id: String,
+    state: SnapshotMsg,
+    players: SavedPlayers,
+}
+
+// Messages for the GameManager
+#[derive(Debug, Deserialize, Serialize)]
+pub enum GameMsg {
+    Snapshot(Box<SnapshotMsg>),
+    Phase(PhaseMsg),
+    PlaceCoins(Versioned<PlaceCoins>),
+
+    // Sent to all clients when the round is over, before moving on to the Results phase
+    Results(Versioned<RoundResults>),
+
+    // Sent to all clients when a series starts, and after each of its rounds
+    Series(Versioned<Series>),
+
+    // Sent to all clients when the arena starts shrinking, and at each later stage
+    Boundary(Versioned<BoundaryMsg>),
+
+    // Sent to all clients when a round starts, with the map it's played on, if any
+    Map(Versioned<Option<MapConfig>>),
+
+    // Sent to all clients when power-ups spawn, are picked up or run out
+    PowerUps(Versioned<PowerUps>),
 }
 
 // .. sent from clients to host
@@ -35,17 +139,59 @@ struct UpdateReadyState {
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct GameManagerConfig {
     pub player_mgr: PlayerManagerConfig,
+    pub connect_timeout: f64, // seconds a client waits for the connection to the host to open
+    pub handshake_timeout: f64, // seconds a client waits for the host to answer its handshake
+    pub reconnect_attempts: u32, // times a client tries to reconnect after losing the host
+    pub reconnect_backoff: f64, // seconds before the first reconnect attempt, doubling each attempt
+    pub reconnect_backoff_max: f64, // most seconds between reconnect attempts
+    pub ping_interval: f64, // seconds between round-trip time measurements
+    pub placement_time: f64, // seconds players have to pick where their snake starts
+    pub mode: GameMode,
+    pub round: RoundConfig,
+    pub power_ups: PowerUpConfig,
+    pub shrink: ShrinkConfig,
+    pub series: SeriesConfig,
+    pub maps: Vec<MapConfig>,
+    pub map: Option<usize>, // index in 'maps' of the map rounds are played on, or None for an empty arena
+    pub practice: PracticeConfig,
+    pub hot_seat: HotSeatConfig,
+    pub replay: ReplayConfig,
+}
+
+impl GameManagerConfig {
+    /// Return the map rounds are played on, if any
+    pub fn selected_map(&self) -> Option<&MapConfig> {
+        self.map.and_then(|idx| self.maps.get(idx))
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct MainMenuUiConfig {
     pub host_button: ButtonConfig,
     pub join_button: ButtonConfig,
+    pub spectate_button: ButtonConfig,
+    pub practice_button: ButtonConfig,
+    pub hot_seat_button: ButtonConfig,
 }
 
 #[derive(Clone, Deserialize, Serialize)]
 pub struct GameManagerUiConfig {
-    pub player_names: TextConfig,
+    pub player_names: TextConfig, // the host's team roster in the lobby, one player per line
+    pub player_names_line_height: f64,
+    pub status_text: TextConfig,
+    pub play_button: ButtonConfig, // for a spectator to ask to play
+    pub add_bot_button: ButtonConfig, // for the host, in the lobby
+    pub start_button: ButtonConfig, // for the host, in the lobby
+    pub placement: PlacementUiConfig,
+    pub round: RoundUiConfig,
+    pub power_ups: PowerUpUiConfig,
+    pub snake: SnakeUiConfig,
+    pub shrink: ShrinkUiConfig,
+    pub map: MapUiConfig,
+    pub series: SeriesUiConfig,
+    pub practice: PracticeUiConfig,
+    pub hot_seat: HotSeatUiConfig,
+    pub replay: ReplayUiConfig,
     pub main_menu: MainMenuUiConfig,
 }
 
@@ -54,11 +200,17 @@ pub struct GameManagerUiConfig {
 pub struct MainMenuManager {
     host_button: Button,
     join_button: Button,
+    spectate_button: Button,
+    practice_button: Button,
+    hot_seat_button: Button,
 }
 
 pub enum MainMenuManagerThinkResult {
     HostGame,
     JoinGame,
+    SpectateGame,
+    Practice,
+    HotSeat,
 }
 
 impl MainMenuManager {
@@ -66,6 +218,9 @@ impl MainMenuManager {
         MainMenuManager {
             host_button: Button::new(),
             join_button: Button::new(),
+            spectate_button: Button::new(),
+            practice_button: Button::new(),
+            hot_seat_button: Button::new(),
         }
     }
 
@@ -82,107 +237,1031 @@ impl MainMenuManager {
             }
         }
 
+        if self.spectate_button.think(game.mouse(), &ui_cfg.spectate_button).into_iter()
+            .any(|res| matches!(res, ButtonThinkResult::Clicked)) {
+            return Some(MainMenuManagerThinkResult::SpectateGame);
+        }
+
+        if self.practice_button.think(game.mouse(), &ui_cfg.practice_button).into_iter()
+            .any(|res| matches!(res, ButtonThinkResult::Clicked)) {
+            return Some(MainMenuManagerThinkResult::Practice);
+        }
+
+        if self.hot_seat_button.think(game.mouse(), &ui_cfg.hot_seat_button).into_iter()
+            .any(|res| matches!(res, ButtonThinkResult::Clicked)) {
+            return Some(MainMenuManagerThinkResult::HotSeat);
+        }
+
         None
     }
 
     fn draw(&self, game: &dyn BaseGame, ui_cfg: &MainMenuUiConfig) {
         self.host_button.draw(game.mouse(), game.painter(), &ui_cfg.host_button);
         self.join_button.draw(game.mouse(), game.painter(), &ui_cfg.join_button);
+        self.spectate_button.draw(game.mouse(), game.painter(), &ui_cfg.spectate_button);
+        self.practice_button.draw(game.mouse(), game.painter(), &ui_cfg.practice_button);
+        self.hot_seat_button.draw(game.mouse(), game.painter(), &ui_cfg.hot_seat_button);
     }
 }
 
 // Enums
-enum GameState {
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum GameState {
     // Waiting for people to join.  Everyone sees a menu, and a button to vote to start
     Lobby,
 
     // Everyone gets a chance to decide where to place their snake
-    _PlacingSnakes,
+    PlacingSnakes,
 
     // Everyone is playing
-    _Playing,
+    Playing,
+
+    // The round is over, everyone sees who won
+    Results,
+}
+
+// MatchState
+// The state of the match beyond the players: owned by the host, replicated to the clients
+pub struct MatchState {
+    version: u32, // bumped by the host on every change
+    phase: GameState,
+    round_end_time: Option<f64>, // when the current phase ends, if it's timed
+    coins: Vec<Pos2d>,
+    zones: Vec<Zone>,
+    scores: HashMap<u32, u32>, // by the player's join order
+    results: Option<RoundResults>, // once the round is over
+    series: Option<Series>, // kept until the next one starts
+    boundary: Option<Boundary>, // while playing in a shrinking arena
+    map: Option<MapConfig>, // of the current round
+    power_ups: PowerUps, // while playing
+    collected: HashMap<u32, u32>, // coins collected this round, by the player's join order
+    round: RoundState, // mode and knocked out players of the current round
+}
+
+impl MatchState {
+    fn new() -> Self {
+        MatchState {
+            version: 0,
+            phase: GameState::Lobby,
+            round_end_time: None,
+            coins: Vec::new(),
+            zones: Vec::new(),
+            scores: HashMap::new(),
+            results: None,
+            series: None,
+            boundary: None,
+            map: None,
+            power_ups: PowerUps::new(),
+            collected: HashMap::new(),
+            round: RoundState::default(),
+        }
+    }
+
+    /// Return a snapshot of this state at time 'now'
+    fn snapshot(&self, now: f64) -> SnapshotMsg {
+        SnapshotMsg {
+            version: self.version,
+            phase: self.phase,
+            round_time_left: self.time_left(now),
+            coins: self.coins.clone(),
+            zones: self.zones.clone(),
+            scores: self.scores.clone(),
+            results: self.results.clone(),
+            series: self.series.clone(),
+            boundary: self.boundary.as_ref().map(|boundary| boundary.msg()),
+            map: self.map.clone(),
+            power_ups: self.power_ups.clone(),
+            collected: self.collected.clone(),
+            round: self.round.clone(),
+        }
+    }
+
+    /// Move on to 'phase' at time 'now', for 'duration' seconds if it's timed
+    fn set_phase(&mut self, now: f64, phase: GameState, duration: Option<f64>) {
+        self.version += 1;
+        self.enter_phase(phase);
+        self.round_end_time = duration.map(|duration| now + duration);
+    }
+
+    /// Move on to 'phase'.  The previous round is forgotten when back in the lobby.
+    fn enter_phase(&mut self, phase: GameState) {
+        self.phase = phase;
+        if phase != GameState::Playing {
+            self.boundary = None;
+            self.power_ups = PowerUps::new();
+        }
+        if phase == GameState::Lobby {
+            self.coins.clear();
+            self.zones.clear();
+            self.scores.clear();
+            self.collected.clear();
+            self.round = RoundState::default();
+            self.results = None;
+        }
+    }
+
+    /// Return the seconds left in the current phase at 'now', if it's timed
+    fn time_left(&self, now: f64) -> Option<f64> {
+        self.round_end_time.map(|end| (end - now).max(0.0))
+    }
+
+    fn phase_msg(&self, now: f64) -> PhaseMsg {
+        PhaseMsg {
+            version: self.version,
+            phase: self.phase,
+            time_left: self.time_left(now),
+        }
+    }
+
+    /// Return the specified 'msg''s update if we don't have it yet, bumping our version
+    /// to its, or None if we already have a newer state
+    fn take_update<T>(&mut self, msg: Versioned<T>) -> Option<T> {
+        if msg.version <= self.version {
+            log(&format!("Ignoring stale update, version {} <= {}", msg.version, self.version));
+            return None;
+        }
+
+        self.version = msg.version;
+        Some(msg.update)
+    }
+
+    /// Apply the phase change in the specified 'msg', received at time 'now'.  Return
+    /// false, doing nothing, if we already have a newer state.
+    fn apply_phase(&mut self, now: f64, msg: &PhaseMsg) -> bool {
+        if msg.version < self.version {
+            return false;
+        }
+
+        self.version = msg.version;
+        self.enter_phase(msg.phase);
+        self.round_end_time = msg.time_left.map(|left| now + left);
+        true
+    }
+
+    /// Draw what's in the arena: the map once a round starts, and the boundary, zones,
+    /// coins and power-ups while playing
+    fn draw_arena(&self, game: &dyn BaseGame, ui_cfg: &GameManagerUiConfig) {
+        if let (Some(map), false) = (&self.map, self.phase == GameState::Lobby) {
+            draw_map(game, map, &ui_cfg.map);
+        }
+
+        if self.phase == GameState::Playing {
+            if let Some(boundary) = &self.boundary {
+                draw_boundary(game, boundary, &ui_cfg.shrink);
+            }
+            draw_zones(game, &self.zones, &ui_cfg.round);
+            draw_coins(game, &self.coins, &ui_cfg.round);
+            draw_pickups(game, &self.power_ups, &ui_cfg.power_ups);
+        }
+    }
+
+    /// Draw what's over the arena: the time left while playing, or the results once
+    /// the round is over, and the series standings between rounds
+    fn draw_overlay(&self, game: &dyn BaseGame, ui_cfg: &GameManagerUiConfig) {
+        match (self.phase, &self.results) {
+            (GameState::Playing, _) => draw_timer(game, self.time_left(game.now()).unwrap_or(0.0), &ui_cfg.round),
+            (GameState::Results, Some(results)) => draw_results(game, results, &ui_cfg.round),
+            _ => {}
+        }
+
+        let between_rounds = self.phase == GameState::Lobby || self.phase == GameState::Results;
+        if let (Some(series), true) = (&self.series, between_rounds) {
+            if series.mode() != SeriesMode::Single {
+                draw_standings(game, series, &ui_cfg.series);
+            }
+        }
+    }
+
+    /// Return true if a series is going on, with more rounds to play
+    fn in_series(&self) -> bool {
+        self.series.as_ref().is_some_and(|series| !series.is_over())
+    }
+
+    /// Replace this state by the one in the specified 'msg', received at time 'now'.
+    /// Return false, doing nothing, if we already have a newer state.
+    fn apply_snapshot(&mut self, now: f64, msg: &SnapshotMsg) -> bool {
+        if msg.version < self.version {
+            return false;
+        }
+
+        self.version = msg.version;
+        self.phase = msg.phase;
+        self.round_end_time = msg.round_time_left.map(|left| now + left);
+        self.coins = msg.coins.clone();
+        self.zones = msg.zones.clone();
+        self.scores = msg.scores.clone();
+        self.results = msg.results.clone();
+        self.series = msg.series.clone();
+        self.boundary = msg.boundary.as_ref().map(Boundary::from_msg);
+        self.map = msg.map.clone();
+        self.power_ups = msg.power_ups.clone();
+        self.collected = msg.collected.clone();
+        self.round = msg.round.clone();
+        true
+    }
 }
 
 // HostGameManager
 pub struct HostGameManager {
-    _state: GameState,
     listen_handle: NetworkHandle,
-    _coins: Vec<Pos2d>,
+    next_listen_time: Option<f64>, // set when listening failed, to try again
+    match_state: MatchState,
     players: HostPlayerManager,
+    add_bot_button: Button,
+    start_button: Button,
+    rematch_button: Button,
+    next_round_button: Button,
+    round: RoundTracker,
+    shrink: ShrinkSchedule,
+    recorder: ReplayRecorder,
+
+    // Lines of the team roster shown in the lobby, with their player's join order
+    roster: Vec<(u32, String)>,
+
+    // How the start points look to our player, while snakes are being placed
+    markers: Vec<(Pos2d, StartPointMarker)>,
 
     // Map from connection to our GameStream for it
     _streams: HashMap<NetworkHandle, StreamHandle>,
+
+    next_ping_time: f64,
+
+    // Coins pulled by a magnet are sent to the clients at most once until then
+    next_coins_time: f64,
 }
 
 impl HostGameManager {
     fn new(game: &mut dyn BaseGame, config: &GameManagerConfig) -> Self {
         HostGameManager {
-            _state: GameState::Lobby,
             listen_handle: game.network().listen("moveaxesp-snake-snatch-game"),
-            _coins: Vec::new(),
+            next_listen_time: None,
+            match_state: MatchState::new(),
             players: HostPlayerManager::new("GameHost", &config.player_mgr),
+            add_bot_button: Button::new(),
+            start_button: Button::new(),
+            rematch_button: Button::new(),
+            next_round_button: Button::new(),
+            round: RoundTracker::new(),
+            shrink: ShrinkSchedule::new(),
+            recorder: ReplayRecorder::new(config, game.now()),
+            roster: Vec::new(),
+            markers: Vec::new(),
+            _streams: HashMap::new(),
+            next_ping_time: game.now() + config.ping_interval,
+            next_coins_time: 0.0,
+        }
+    }
+
+    /// Take over the game with the specified 'players' and 'match_state', after the
+    /// previous host left (or from a saved game)
+    fn from_client(game: &mut dyn BaseGame, config: &GameManagerConfig, mut players: HostPlayerManager,
+                   mut match_state: MatchState) -> Self {
+        log("Taking over as the host");
+
+        // Clients that had the previous host's latest state should take ours
+        match_state.version += 1;
+
+        if match_state.phase == GameState::PlacingSnakes {
+            players.start_placing();
+        }
+
+        // Pick up the round where the previous host left it
+        let round = match match_state.phase {
+            GameState::Playing => RoundTracker::resume(&match_state.round, &players.snapshot(), &match_state.collected,
+                                                       &config.round),
+            _ => RoundTracker::new(),
+        };
+
+        HostGameManager {
+            listen_handle: game.network().listen("moveaxesp-snake-snatch-game"),
+            next_listen_time: None,
+            match_state,
+            players,
+            add_bot_button: Button::new(),
+            start_button: Button::new(),
+            rematch_button: Button::new(),
+            next_round_button: Button::new(),
+            round,
+            shrink: ShrinkSchedule::new(),
+            recorder: ReplayRecorder::new(config, game.now()),
+            roster: Vec::new(),
+            markers: Vec::new(),
             _streams: HashMap::new(),
+            next_ping_time: game.now() + config.ping_interval,
+            next_coins_time: 0.0,
+        }
+    }
+
+    /// Return everything we know about the game at 'now', to 'restore' later
+    fn save(&self, now: f64) -> SavedGame {
+        SavedGame {
+            build_id: BUILD_ID.to_string(),
+            state: self.match_state.snapshot(now),
+            players: self.players.save(),
+        }
+    }
+
+    /// Host the 'saved' game.  Clients that were in it can rejoin as their player.
+    fn restore(game: &mut dyn BaseGame, config: &GameManagerConfig, saved: SavedGame) -> Self {
+        if saved.build_id != BUILD_ID {
+            log(&format!("Restoring game saved by build {}, we're {}", &saved.build_id, BUILD_ID));
+        }
+
+        let mut match_state = MatchState::new();
+        match_state.apply_snapshot(game.now(), &saved.state);
+
+        let players = HostPlayerManager::restore(game, saved.players, &config.player_mgr);
+        Self::from_client(game, config, players, match_state)
+    }
+
+    /// Move the match on to 'phase', for 'duration' seconds if it's timed, and tell the clients
+    fn set_phase(&mut self, game: &mut dyn BaseGame, phase: GameState, duration: Option<f64>) {
+        let now = game.now();
+        self.match_state.set_phase(now, phase, duration);
+        self.broadcast(game, || GameMsg::Phase(self.match_state.phase_msg(now)));
+    }
+
+    /// Send the specified game 'msg' to all the clients
+    fn broadcast(&self, game: &mut dyn BaseGame, msg: impl Fn() -> GameMsg) {
+        for handle in self.players.client_handles() {
+            game.network().send(&handle.default_stream(), NetMsg::Game(msg()));
         }
     }
 
-    fn think(&mut self, game: &mut dyn BaseGame, config: &GameManagerConfig, _ui_cfg: &GameManagerUiConfig) {
+    /// Bump the version of the match state, which the specified 'update' changed, and
+    /// send the update to all the clients as the 'msg' kind of game message
+    fn broadcast_update<T: Clone>(&mut self, game: &mut dyn BaseGame, update: T, msg: fn(Versioned<T>) -> GameMsg) {
+        self.match_state.version += 1;
+        let version = self.match_state.version;
+        self.broadcast(game, || msg(Versioned { version, update: update.clone() }));
+    }
+
+    /// Tell all the clients where the coins and zones are, the scores, how many coins
+    /// each player collected, and who's been knocked out
+    fn send_coins(&mut self, game: &mut dyn BaseGame) {
+        self.match_state.collected = self.round.coins_collected();
+        self.match_state.round = self.round.state().clone();
+        let coins = PlaceCoins {
+            coins: self.match_state.coins.clone(),
+            zones: self.match_state.zones.clone(),
+            scores: self.match_state.scores.clone(),
+            collected: self.match_state.collected.clone(),
+            round: Box::new(self.match_state.round.clone()),
+        };
+        self.broadcast_update(game, coins, GameMsg::PlaceCoins);
+    }
+
+    /// Start the next round of the series: line up its players, and let them place their snakes
+    fn start_round(&mut self, game: &mut dyn BaseGame, config: &GameManagerConfig) {
+        if let Some(series) = &mut self.match_state.series {
+            let present: Vec<u32> = self.players.entrants().into_iter().map(|(order, _)| order).collect();
+            series.remove_absent(&present, &config.series);
+
+            if let Some(lineup) = series.lineup() {
+                self.players.set_lineup(game, lineup);
+            }
+
+            let series = series.clone();
+            self.broadcast_update(game, series, GameMsg::Series);
+        }
+
+        // The config may have been updated since the last round
+        let map = config.selected_map().cloned();
+        self.match_state.map = map.clone();
+        self.broadcast_update(game, map, GameMsg::Map);
+
+        self.players.start_placing();
+        self.set_phase(game, GameState::PlacingSnakes, Some(config.placement_time));
+    }
+
+    /// Apply the round's rules to this frame, shrinking the full 'arena' if it does,
+    /// and end the round if someone won
+    fn think_round(&mut self, game: &mut dyn BaseGame, config: &GameManagerConfig, arena: &ArenaRect) {
+        let round_time = config.round.round_time - self.match_state.time_left(game.now()).unwrap_or(0.0);
+        let mut moved = None;
+        if let Some(boundary) = &mut self.match_state.boundary {
+            boundary.think(game.elapsed_time());
+            if self.shrink.think(round_time, boundary, arena, &config.shrink) {
+                moved = Some(boundary.msg());
+            }
+        }
+        if let Some(msg) = moved {
+            self.broadcast_update(game, msg, GameMsg::Boundary);
+        }
+        let mut eliminated = false;
+        if let Some(boundary) = &self.match_state.boundary {
+            let rect = boundary.rect();
+            self.players.trim_snakes(&rect);
+            eliminated = self.round.eliminate_outside(&self.players.snapshot(), |pos| rect.contains(pos));
+        }
+
+        // Clients' snakes only count up to where they went through an obstacle
+        let mut players = self.players.snapshot();
+        if let Some(map) = &self.match_state.map {
+            for player in players.iter_mut() {
+                map.clip(&mut player.snake_points);
+            }
+        }
+
+        let elapsed = game.elapsed_time();
+        if self.match_state.power_ups.think(elapsed, &players, &config.power_ups, || random_coin(&config.round)) {
+            let power_ups = self.match_state.power_ups.clone();
+            self.broadcast_update(game, power_ups, GameMsg::PowerUps);
+        }
+
+        let state = &mut self.match_state;
+        let attracted = state.power_ups.attract(&mut state.coins, &players, elapsed, &config.power_ups);
+        let changed = self.round.think(elapsed, RoundFrame {
+            players: &players,
+            coins: &mut state.coins,
+            zones: &mut state.zones,
+            scores: &mut state.scores,
+            power_ups: &state.power_ups,
+        }, &config.round, &config.player_mgr.snake);
+        let now = game.now();
+        if changed || eliminated || (attracted && now >= self.next_coins_time) {
+            self.send_coins(game);
+            self.next_coins_time = now + 0.1;
+        }
+
+        let time_up = self.match_state.time_left(game.now()) == Some(0.0);
+        if let Some(results) = self.round.results(&players, &self.match_state.scores, time_up, &config.round,
+                                                   &config.player_mgr.teams) {
+            let winner = results.winner.and_then(|order| results.stats.get(&order)).map(|stats| stats.name.clone());
+            log(&format!("Round over, winner: {:?}", winner));
+
+            self.broadcast_update(game, results.clone(), GameMsg::Results);
+            if let Some(series) = &mut self.match_state.series {
+                series.record(&results, &config.series);
+                let series = series.clone();
+                self.broadcast_update(game, series, GameMsg::Series);
+            }
+            self.match_state.results = Some(results);
+            self.set_phase(game, GameState::Results, None);
+        }
+    }
+
+    /// Close every connection, before we're dropped
+    fn shutdown(&self, game: &mut dyn BaseGame) {
+        for handle in self.players.client_handles() {
+            game.network().close(handle);
+        }
+        game.network().close(self.listen_handle);
+    }
+
+    fn think(&mut self, game: &mut dyn BaseGame, config: &GameManagerConfig, ui_cfg: &GameManagerUiConfig,
+             arena: &ArenaRect) {
         for msg in game.network().get_handle_events(self.listen_handle).into_iter() {
-            if let NetUpdate::NewPeer(new_corr) = msg {
-                // Inform any connecting peers about possible start points
-                let client_handle = NetworkHandle::from_correlator(new_corr);
-                self.players.add_client(client_handle);
+            match msg {
+                NetUpdate::NewPeer(new_corr) => {
+                    // Inform any connecting peers about possible start points
+                    let client_handle = NetworkHandle::from_correlator(new_corr);
+                    self.players.add_client(client_handle);
+                }
+                NetUpdate::ListenFail => {
+                    // After taking over, the previous host may still hold the address for a bit
+                    log(&format!("Listening failed on handle {}, trying again", self.listen_handle));
+                    game.network().close(self.listen_handle);
+                    self.next_listen_time = Some(game.now() + config.reconnect_backoff);
+                }
+                _ => {}
+            }
+        }
+
+        let now = game.now();
+        if let Some(listen_time) = self.next_listen_time {
+            if now >= listen_time {
+                self.listen_handle = game.network().listen("moveaxesp-snake-snatch-game");
+                self.next_listen_time = None;
+            }
+        }
+
+        if now >= self.next_ping_time {
+            for handle in self.players.client_handles() {
+                game.network().send(&handle.default_stream(), NetMsg::Ping(now));
+            }
+            self.next_ping_time = now + config.ping_interval;
+        }
+
+        // In a bracket, the series decides who plays
+        let bracket = self.match_state.in_series() &&
+            self.match_state.series.as_ref().is_some_and(|series| series.lineup().is_some());
+        if self.match_state.phase != GameState::Playing && !bracket {
+            self.players.promote_spectators(game);
+        }
+
+        if self.match_state.phase == GameState::Lobby {
+            for res in self.add_bot_button.think(game.mouse(), &ui_cfg.add_bot_button) {
+                match res {
+                    ButtonThinkResult::Clicked => {
+                        if !self.players.add_bot(game, &config.player_mgr) {
+                            log("No room left for a bot");
+                        }
+                    }
+                }
+            }
+
+            for res in self.start_button.think(game.mouse(), &ui_cfg.start_button) {
+                match res {
+                    ButtonThinkResult::Clicked => {
+                        if !self.match_state.in_series() {
+                            let series = Series::new(&config.series, self.players.entrants());
+                            self.match_state.series = Some(series);
+                        }
+                        self.start_round(game, config);
+                    }
+                }
+            }
+
+            // Clicking a player in the roster moves it to the next team
+            let names = &ui_cfg.player_names;
+            for (line, (order, _)) in self.roster.iter().enumerate() {
+                let pos = (names.offset.x, names.offset.y + line as f64 * ui_cfg.player_names_line_height).into();
+                if game.mouse().is_click_in_rect(&pos, 400.0, ui_cfg.player_names_line_height) {
+                    self.players.cycle_team(*order, &config.player_mgr);
+                }
+            }
+
+            let teams = &config.player_mgr.teams;
+            self.roster = self.players.roster().into_iter()
+                .filter_map(|(order, name, team)| {
+                    let team = teams.get(team? as usize)?;
+                    Some((order, format!("{}: {}", &team.name, name)))
+                })
+                .collect();
+        }
+
+        if self.match_state.phase == GameState::PlacingSnakes {
+            if let Some(pos) = clicked_start_point(game.mouse(), &self.markers, &ui_cfg.placement) {
+                self.players.claim_for_host(now, pos, &config.player_mgr);
+            }
+            self.markers = self.players.start_point_markers(&config.player_mgr);
+
+            if self.match_state.time_left(now) == Some(0.0) {
+                self.players.finish_placing(game, &config.player_mgr);
+
+                let state = &mut self.match_state;
+                self.round.start(&self.players.snapshot(), &mut state.coins, &mut state.zones, &mut state.scores,
+                                 config.mode, &config.round);
+                self.send_coins(game);
+                self.set_phase(game, GameState::Playing, Some(config.round.round_time));
+
+                self.match_state.power_ups.reset(&config.power_ups);
+                let power_ups = self.match_state.power_ups.clone();
+                self.broadcast_update(game, power_ups, GameMsg::PowerUps);
+
+                if config.mode == GameMode::ShrinkingArena {
+                    let boundary = Boundary::new(*arena);
+                    let msg = boundary.msg();
+                    self.match_state.boundary = Some(boundary);
+                    self.broadcast_update(game, msg, GameMsg::Boundary);
+                    self.shrink.reset();
+                }
+            }
+        }
+
+        if self.match_state.phase == GameState::Playing {
+            // Players who joined during the round get in right away, unless the
+            // series decides who plays
+            if !self.match_state.in_series() {
+                self.players.place_waiting(game, &config.player_mgr);
+            }
+            self.think_round(game, config, arena);
+        }
+
+        if self.match_state.phase == GameState::Results {
+            if self.match_state.in_series() {
+                for res in self.next_round_button.think(game.mouse(), &ui_cfg.series.next_round_button) {
+                    match res {
+                        ButtonThinkResult::Clicked => {
+                            self.players.reset_snakes(game);
+                            self.start_round(game, config);
+                        }
+                    }
+                }
+            }
+            else {
+                for res in self.rematch_button.think(game.mouse(), &ui_cfg.round.rematch_button) {
+                    match res {
+                        ButtonThinkResult::Clicked => {
+                            self.players.reset_snakes(game);
+
+                            // Whoever sat out the last bracket match plays again
+                            let everyone: Vec<u32> = self.players.entrants().into_iter().map(|(order, _)| order).collect();
+                            self.players.set_lineup(game, &everyone);
+                            self.set_phase(game, GameState::Lobby, None);
+                        }
+                    }
+                }
             }
         }
 
-        self.players.think(game, &config.player_mgr);
+        let objectives: Vec<Pos2d> = self.match_state.coins.iter().copied()
+            .chain(self.match_state.zones.iter().map(|zone| zone.pos))
+            .collect();
+        self.players.aim_bots(&objectives);
+
+        // Bring clients that just joined up to date with the match
+        self.players.apply_power_ups(&self.match_state.power_ups, &config.power_ups, &ui_cfg.power_ups);
+        self.players.apply_length_budgets(&self.match_state.collected, self.match_state.round.times_snatched());
+        for handle in self.players.think(game, &config.player_mgr, self.match_state.map.as_ref()) {
+            let snapshot = self.match_state.snapshot(game.now());
+            game.network().send(&handle.default_stream(), NetMsg::Game(GameMsg::Snapshot(Box::new(snapshot))));
+        }
+
+        if self.recorder.wants_frame(now) {
+            self.recorder.record(now, &config.replay, self.players.snapshot(),
+                                 &self.match_state.coins, &self.match_state.scores);
+        }
     }
 
-    fn draw(&self, game: &dyn BaseGame, _ui_cfg: &GameManagerUiConfig) {
-        self.players.draw(game);
+    fn draw(&self, game: &dyn BaseGame, ui_cfg: &GameManagerUiConfig) {
+        self.players.draw(game, self.match_state.map.as_ref(), &ui_cfg.snake);
+
+        if self.match_state.phase == GameState::Lobby {
+            self.add_bot_button.draw(game.mouse(), game.painter(), &ui_cfg.add_bot_button);
+            self.start_button.draw(game.mouse(), game.painter(), &ui_cfg.start_button);
+
+            for (line, (_, text)) in self.roster.iter().enumerate() {
+                let pos = (0.0, line as f64 * ui_cfg.player_names_line_height).into();
+                game.painter().draw_text(text, &pos, 400.0, &ui_cfg.player_names);
+            }
+        }
+
+        if self.match_state.phase == GameState::PlacingSnakes {
+            draw_start_points(game, &self.markers, &ui_cfg.placement);
+            draw_countdown(game, self.match_state.time_left(game.now()).unwrap_or(0.0), &ui_cfg.placement);
+        }
+
+        self.match_state.draw_arena(game, ui_cfg);
+        self.match_state.draw_overlay(game, ui_cfg);
+        if self.match_state.phase == GameState::Results && self.match_state.in_series() {
+            self.next_round_button.draw(game.mouse(), game.painter(), &ui_cfg.series.next_round_button);
+        }
+        else if self.match_state.phase == GameState::Results {
+            self.rematch_button.draw(game.mouse(), game.painter(), &ui_cfg.round.rematch_button);
+        }
     }
 }
 
 // ClientGameManager
+
+// Progress of a client's connection to the host
+enum ConnectionState {
+    // Waiting for the connection, started at the contained time, to open
+    Connecting(f64),
+
+    // Handshake sent at the contained time, waiting for the host's reply
+    Handshake(f64),
+
+    // The host accepted us
+    Joined,
+
+    // Lost the connection to the host, trying again at the contained time
+    Reconnecting(f64),
+
+    // We can't play with this host.  Contains a user-readable reason
+    Failed(String),
+}
+
+pub enum ClientGameManagerThinkResult {
+    // The host left, and we're taking over with the contained players and match state
+    BecomeHost(HostPlayerManager, MatchState),
+}
+
 pub struct ClientGameManager {
-    _state: GameState,
-    _coins: Vec<Pos2d>,
+    match_state: MatchState,
     players: Option<ClientPlayerManager>,
     host_handle: NetworkHandle,
     _host_stream: Option<StreamHandle>,
+    conn_state: ConnectionState,
+    next_ping_time: f64,
+
+    // Given by the host when it accepts us, to resume our player after reconnecting
+    session_token: Option<String>,
+    join_order: Option<u32>,
+
+    // Number of reconnect attempts since we were last joined
+    reconnect_attempt: u32,
+
+    // Whether to join as a spectator.  Kept up to date, to rejoin the same way.
+    spectate: bool,
+
+    // For spectators to look around, by dragging the view
+    camera: Pos2d,
+    drag_pos: Option<Pos2d>,
+    play_button: Button,
+
+    // Records the match as we see it
+    recorder: ReplayRecorder,
 }
 
 impl ClientGameManager {
-    fn new(game:&mut dyn BaseGame) -> Self {
+    fn new(game:&mut dyn BaseGame, config: &GameManagerConfig, spectate: bool) -> Self {
         ClientGameManager {
-            _state: GameState::Lobby,
-            _coins: Vec::new(),
+            match_state: MatchState::new(),
             players: None,
             host_handle: game.network().connect("moveaxesp-snake-snatch-game"),
-            _host_stream: None
+            _host_stream: None,
+            conn_state: ConnectionState::Connecting(game.now()),
+            next_ping_time: 0.0,
+            session_token: None,
+            join_order: None,
+            reconnect_attempt: 0,
+            spectate,
+            camera: (0,0).into(),
+            drag_pos: None,
+            play_button: Button::new(),
+            recorder: ReplayRecorder::new(config, game.now()),
         }
     }
 
-    fn think(&mut self, game: &mut dyn BaseGame, config: &GameManagerConfig) {
+    /// Give up on the host, showing the specified 'reason' to the user
+    fn fail(&mut self, game: &mut dyn BaseGame, reason: String) {
+        log(&format!("Can't join host {} :: {}", self.host_handle, &reason));
+        self.players = None;
+        game.network().close(self.host_handle);
+        self.conn_state = ConnectionState::Failed(reason);
+    }
+
+    /// Handle the connection to the host closing or failing to open.  If we were in
+    /// the game, try to get back in it after a delay.
+    fn connection_lost(&mut self, game: &mut dyn BaseGame, config: &GameManagerConfig) {
+        if self.session_token.is_none() {
+            self.fail(game, "Lost connection to the host".to_string());
+            return;
+        }
+
+        if self.reconnect_attempt >= config.reconnect_attempts {
+            self.fail(game, "Lost connection to the host, and could not reconnect".to_string());
+            return;
+        }
+
+        // Keep our view of the players.  The host rebuilds it once we're back, or it
+        // becomes the game if we take over as the host.
+        if let Some(mgr) = &mut self.players {
+            mgr.connection_lost(self.host_handle);
+        }
+        game.network().close(self.host_handle);
+
+        let delay = config.reconnect_backoff * 2.0_f64.powi(self.reconnect_attempt as i32);
+        self.reconnect_attempt += 1;
+        log(&format!("Reconnecting to host in {}s (attempt {})", delay, self.reconnect_attempt));
+        self.conn_state = ConnectionState::Reconnecting(game.now() + delay.min(config.reconnect_backoff_max));
+    }
+
+    /// Return true if we're next in line to host, should the host leave, as the host
+    /// last told every client
+    fn is_successor(&self) -> bool {
+        match (self.join_order, &self.players) {
+            (Some(order), Some(mgr)) => !self.spectate && mgr.successor() == Some(order),
+            _ => false,
+        }
+    }
+
+    fn think(&mut self, game: &mut dyn BaseGame, config: &GameManagerConfig, ui_cfg: &GameManagerUiConfig)
+    -> Option<ClientGameManagerThinkResult> {
+        match self.conn_state {
+            ConnectionState::Failed(_) => return None,
+            ConnectionState::Reconnecting(next_try) => {
+                if game.now() < next_try {
+                    return None;
+                }
+
+                self.host_handle = game.network().connect("moveaxesp-snake-snatch-game");
+                self.conn_state = ConnectionState::Connecting(game.now());
+                return None;
+            }
+            _ => {}
+        }
+
         for outer in game.network().get_handle_events(self.host_handle).into_iter() {
             match outer {
                 NetUpdate::NewPeer(_) => {
                     log(&format!("Successfully connected to host with handle {}", self.host_handle));
-                    self.players = Some(ClientPlayerManager::new("GameClient", self.host_handle, game));
+                    self.players = Some(ClientPlayerManager::new("GameClient", self.host_handle, self.spectate,
+                                                                 self.session_token.clone(), self.join_order, game));
+                    self.conn_state = ConnectionState::Handshake(game.now());
+                }
+                NetUpdate::ConnectFail if self.reconnect_attempt > 0 && self.is_successor() => {
+                    // Nobody is listening at the host's address anymore: the host left,
+                    // and it's our turn
+                    game.network().close(self.host_handle);
+                    let players = self.players.take().unwrap();
+                    let host_players = HostPlayerManager::from_client(game, players, self.join_order.unwrap(), &config.player_mgr);
+                    let match_state = std::mem::replace(&mut self.match_state, MatchState::new());
+                    return Some(ClientGameManagerThinkResult::BecomeHost(host_players, match_state));
                 }
                 _ => {
                     log(&format!("Connect failed/closed: {:?}", outer));
+                    self.connection_lost(game, config);
+                    return None;
+                }
+            }
+        }
+
+        for outer in game.network().get_stream_msgs(self.host_handle.default_stream()) {
+            match outer {
+                NetMsg::NewClientReply(NewClientReplyMsg::Accepted(msg)) => {
+                    log(&format!("Host {} accepted us", self.host_handle));
+                    self.session_token = Some(msg.session_token);
+                    self.join_order = Some(msg.join_order);
+                    self.reconnect_attempt = 0;
+                    self.spectate = msg.spectator;
+                    if let Some(mgr) = &mut self.players {
+                        mgr.set_spectating(msg.spectator);
+                        mgr.set_join_order(msg.join_order);
+                    }
+                    self.conn_state = ConnectionState::Joined;
+                }
+                NetMsg::NewClientReply(NewClientReplyMsg::Rejected(reason)) => {
+                    self.fail(game, reason);
+                    return None;
+                }
+                NetMsg::Game(GameMsg::Snapshot(msg)) => {
+                    if !self.match_state.apply_snapshot(game.now(), &msg) {
+                        log(&format!("Ignoring stale snapshot, version {} < {}", msg.version, self.match_state.version));
+                    }
+                }
+                NetMsg::Game(GameMsg::PlaceCoins(msg)) => {
+                    if let Some(msg) = self.match_state.take_update(msg) {
+                        self.match_state.coins = msg.coins;
+                        self.match_state.zones = msg.zones;
+                        self.match_state.scores = msg.scores;
+                        self.match_state.collected = msg.collected;
+                        self.match_state.round = *msg.round;
+                    }
+                }
+                NetMsg::Game(GameMsg::Results(msg)) => {
+                    if let Some(results) = self.match_state.take_update(msg) {
+                        self.match_state.results = Some(results);
+                    }
+                }
+                NetMsg::Game(GameMsg::Series(msg)) => {
+                    if let Some(series) = self.match_state.take_update(msg) {
+                        self.match_state.series = Some(series);
+                    }
+                }
+                NetMsg::Game(GameMsg::Boundary(msg)) => {
+                    if let Some(msg) = self.match_state.take_update(msg) {
+                        self.match_state.boundary = Some(Boundary::from_msg(&msg));
+                    }
+                }
+                NetMsg::Game(GameMsg::Map(msg)) => {
+                    if let Some(map) = self.match_state.take_update(msg) {
+                        self.match_state.map = map;
+                    }
+                }
+                NetMsg::Game(GameMsg::PowerUps(msg)) => {
+                    if let Some(power_ups) = self.match_state.take_update(msg) {
+                        self.match_state.power_ups = power_ups;
+                    }
+                }
+                NetMsg::Game(GameMsg::Phase(msg)) => {
+                    if !self.match_state.apply_phase(game.now(), &msg) {
+                        log(&format!("Ignoring stale phase, version {} < {}", msg.version, self.match_state.version));
+                    }
                 }
+                NetMsg::StartPointsUpdate(points) => {
+                    if let Some(mgr) = &mut self.players {
+                        mgr.update_start_points(&points);
+                    }
+                }
+                NetMsg::Ping(time) => handle_ping(game, &self.host_handle.default_stream(), time),
+                _ => {
+                    log(&format!("Unexpected message over default stream {} :: {:?}", self.host_handle.default_stream(), outer));
+                }
+            }
+        }
+
+        if let ConnectionState::Connecting(start_time) = self.conn_state {
+            if game.now() - start_time > config.connect_timeout {
+                log(&format!("Timed out connecting to host {}", self.host_handle));
+                self.connection_lost(game, config);
+                return None;
+            }
+        }
+
+        if let ConnectionState::Handshake(sent_time) = self.conn_state {
+            if game.now() - sent_time > config.handshake_timeout {
+                // Hosts too old to know about the handshake never answer it
+                self.fail(game, "Host did not answer. It may be running an incompatible version".to_string());
+                return None;
+            }
+        }
+
+        if let ConnectionState::Joined = self.conn_state {
+            let now = game.now();
+            if now >= self.next_ping_time {
+                game.network().send(&self.host_handle.default_stream(), NetMsg::Ping(now));
+                self.next_ping_time = now + config.ping_interval;
             }
         }
 
+        // Effects run out on their own, the host only tells us about new ones
+        self.match_state.power_ups.tick(game.elapsed_time());
         if let Some(mgr) = &mut self.players {
-            mgr.think(game, &config.player_mgr);
+            mgr.apply_power_ups(&self.match_state.power_ups, &config.power_ups, &ui_cfg.power_ups);
+            mgr.apply_length_budgets(&self.match_state.collected, self.match_state.round.times_snatched());
+            mgr.think(game, &config.player_mgr, self.match_state.map.as_ref());
         }
+
+        if let (ConnectionState::Joined, Some(mgr)) = (&self.conn_state, &mut self.players) {
+            if self.spectate && !mgr.is_spectating() {
+                log("Promoted from spectator to player");
+                self.camera = (0,0).into();
+                self.drag_pos = None;
+            }
+            self.spectate = mgr.is_spectating();
+
+            if let Some(boundary) = &mut self.match_state.boundary {
+                boundary.think(game.elapsed_time());
+                mgr.trim_snakes(&boundary.rect());
+            }
+
+            let now = game.now();
+            if self.recorder.wants_frame(now) {
+                self.recorder.record(now, &config.replay, mgr.snapshot(),
+                                     &self.match_state.coins, &self.match_state.scores);
+            }
+
+            if self.match_state.phase == GameState::PlacingSnakes && !self.spectate {
+                let markers = mgr.start_point_markers();
+                if let Some(pos) = clicked_start_point(game.mouse(), &markers, &ui_cfg.placement) {
+                    mgr.claim_start_point(game, pos);
+                }
+            }
+
+            if self.spectate {
+                for res in self.play_button.think(game.mouse(), &ui_cfg.play_button) {
+                    match res {
+                        ButtonThinkResult::Clicked => mgr.request_play(game),
+                    }
+                }
+
+                self.think_camera(game);
+            }
+        }
+
+        None
     }
 
-    fn draw(&self, game: &dyn BaseGame) {
+    /// Move the camera by however much the mouse was dragged since the last frame
+    fn think_camera(&mut self, game: &dyn BaseGame) {
+        if !game.mouse().is_down() {
+            self.drag_pos = None;
+            return;
+        }
+
+        let pos = game.mouse().pos();
+        if let Some(prev) = self.drag_pos {
+            self.camera = (self.camera.x + prev.x - pos.x, self.camera.y + prev.y - pos.y).into();
+        }
+        self.drag_pos = Some(pos);
+    }
+
+    fn draw(&self, game: &dyn BaseGame, ui_cfg: &GameManagerUiConfig) {
+        let status = match &self.conn_state {
+            ConnectionState::Connecting(_) | ConnectionState::Reconnecting(_) if self.reconnect_attempt > 0 => {
+                Some(format!("Connection lost, reconnecting (attempt {})...", self.reconnect_attempt))
+            }
+            ConnectionState::Connecting(_) => Some("Connecting to host...".to_string()),
+            ConnectionState::Handshake(_) => Some("Joining game...".to_string()),
+            ConnectionState::Joined => None,
+            ConnectionState::Reconnecting(_) => Some("Connection lost, reconnecting...".to_string()),
+            ConnectionState::Failed(reason) => Some(reason.clone()),
+        };
+        if let Some(text) = status {
+            game.painter().draw_text(&text, &(0,0).into(), 1600.0, &ui_cfg.status_text);
+        }
+
         if let Some(mgr) = &self.players {
-            mgr.draw(game);
+            let canvas = game.painter().canvas();
+            canvas.save();
+            canvas.translate(-self.camera.x, -self.camera.y).expect("camera");
+            mgr.draw(game, self.match_state.map.as_ref(), &ui_cfg.snake);
+            self.match_state.draw_arena(game, ui_cfg);
+
+            if self.match_state.phase == GameState::PlacingSnakes {
+                draw_start_points(game, &mgr.start_point_markers(), &ui_cfg.placement);
+            }
+            canvas.restore();
+
+            self.match_state.draw_overlay(game, ui_cfg);
+            if self.match_state.phase == GameState::PlacingSnakes {
+                draw_countdown(game, self.match_state.time_left(game.now()).unwrap_or(0.0), &ui_cfg.placement);
+            }
+        }
+
+        if let (ConnectionState::Joined, true) = (&self.conn_state, self.spectate) {
+            game.painter().draw_text("Spectating", &(0,0).into(), 1600.0, &ui_cfg.status_text);
+            self.play_button.draw(game.mouse(), game.painter(), &ui_cfg.play_button);
+        }
+        else if let (ConnectionState::Joined, Some(mgr)) = (&self.conn_state, &self.players) {
+            let in_round = matches!(self.match_state.phase, GameState::Playing | GameState::Results);
+            if in_round && !mgr.has_snake() {
+                game.painter().draw_text("Waiting for the next round", &(0,0).into(), 1600.0, &ui_cfg.status_text);
+            }
         }
     }
 }
@@ -191,6 +1270,9 @@ pub enum GameManager {
     MainMenu(MainMenuManager),
     Host(HostGameManager),
     Client(ClientGameManager),
+    Replay(ReplayViewer),
+    Practice(PracticeManager),
+    HotSeat(HotSeatManager),
 }
 
 impl GameManager {
@@ -198,18 +1280,105 @@ impl GameManager {
         GameManager::MainMenu(MainMenuManager::new())
     }
 
-    pub fn think(&mut self, game: &mut dyn BaseGame, config: &GameManagerConfig, ui_cfg: &GameManagerUiConfig) {
+    /// Handle per-frame processing.  'arena' is the full arena, as drawn.
+    pub fn think(&mut self, game: &mut dyn BaseGame, config: &GameManagerConfig, ui_cfg: &GameManagerUiConfig,
+                 arena: &ArenaRect) {
         match self {
             Self::MainMenu(mgr) => {
                 if let Some(res) = mgr.think(game, &ui_cfg.main_menu) {
                     match res {
                         MainMenuManagerThinkResult::HostGame => *self = GameManager::Host(HostGameManager::new(game, config)),
-                        MainMenuManagerThinkResult::JoinGame => *self = GameManager::Client(ClientGameManager::new(game)),
+                        MainMenuManagerThinkResult::JoinGame => *self = GameManager::Client(ClientGameManager::new(game, config, false)),
+                        MainMenuManagerThinkResult::SpectateGame => *self = GameManager::Client(ClientGameManager::new(game, config, true)),
+                        MainMenuManagerThinkResult::Practice => {
+                            *self = GameManager::Practice(PracticeManager::new(game, &config.practice, &config.round))
+                        }
+                        MainMenuManagerThinkResult::HotSeat => *self = GameManager::HotSeat(HotSeatManager::new(game, config, arena)),
                     }
                 }
             },
-            Self::Host(mgr) => mgr.think(game, config, ui_cfg),
-            Self::Client(mgr) => mgr.think(game, config),
+            Self::Host(mgr) => mgr.think(game, config, ui_cfg, arena),
+            Self::Client(mgr) => {
+                if let Some(res) = mgr.think(game, config, ui_cfg) {
+                    match res {
+                        ClientGameManagerThinkResult::BecomeHost(players, match_state) => {
+                            *self = GameManager::Host(HostGameManager::from_client(game, config, players, match_state))
+                        }
+                    }
+                }
+            },
+            Self::Replay(mgr) => {
+                if let Some(res) = mgr.think(game, &ui_cfg.replay) {
+                    match res {
+                        ReplayViewerThinkResult::Exit => *self = GameManager::new(),
+                    }
+                }
+            },
+            Self::Practice(mgr) => {
+                let snake_cfg = &config.player_mgr.snake;
+                if let Some(res) = mgr.think(game, &config.practice, &config.round, snake_cfg, &ui_cfg.practice) {
+                    match res {
+                        PracticeManagerThinkResult::Exit => *self = GameManager::new(),
+                    }
+                }
+            },
+            Self::HotSeat(mgr) => {
+                if let Some(res) = mgr.think(game, config, ui_cfg, arena) {
+                    match res {
+                        HotSeatManagerThinkResult::Exit => *self = GameManager::new(),
+                    }
+                }
+            },
+        }
+    }
+
+    /// Return what was recorded of the match so far, if we're in one
+    pub fn replay(&self) -> Option<&Replay> {
+        match self {
+            Self::Host(mgr) => Some(mgr.recorder.replay()),
+            Self::Client(mgr) => Some(mgr.recorder.replay()),
+            _ => None,
+        }
+    }
+
+    /// Return everything we know about the game at 'now', if we're hosting one
+    pub fn save_host(&self, now: f64) -> Option<SavedGame> {
+        match self {
+            Self::Host(mgr) => Some(mgr.save(now)),
+            _ => None,
+        }
+    }
+
+    /// Host the 'saved' game, instead of any we're hosting.  Return false if we're
+    /// in another host's game.
+    pub fn restore_host(&mut self, game: &mut dyn BaseGame, config: &GameManagerConfig, saved: SavedGame) -> bool {
+        match self {
+            Self::Client(_) => return false,
+            Self::Host(mgr) => mgr.shutdown(game),
+            _ => {}
+        }
+
+        *self = GameManager::Host(HostGameManager::restore(game, config, saved));
+        true
+    }
+
+    /// Watch the specified 'replay', if we're not in a match.  Return false otherwise.
+    pub fn view_replay(&mut self, replay: Replay) -> bool {
+        match self {
+            Self::MainMenu(_) | Self::Replay(_) => {
+                *self = GameManager::Replay(ReplayViewer::new(replay));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Return the offset of the view of the arena
+    pub fn camera(&self) -> Pos2d {
+        match self {
+            Self::Client(mgr) => mgr.camera,
+            Self::Replay(mgr) => mgr.camera,
+            _ => (0,0).into(),
         }
     }
 
@@ -217,7 +1386,10 @@ impl GameManager {
         match self {
             Self::MainMenu(mgr) => mgr.draw(game, &ui_cfg.main_menu),
             Self::Host(mgr) => mgr.draw(game, ui_cfg),
-            Self::Client(mgr) => mgr.draw(game),
+            Self::Client(mgr) => mgr.draw(game, ui_cfg),
+            Self::Replay(mgr) => mgr.draw(game, ui_cfg),
+            Self::Practice(mgr) => mgr.draw(game, &ui_cfg.round, &ui_cfg.snake, &ui_cfg.practice),
+            Self::HotSeat(mgr) => mgr.draw(game, ui_cfg),
         }
     }
 }
@@ -0,0 +1,233 @@
+// Hot-seat play: several people sharing one device, each with a local snake bound to
+// its own input (keys, a touch region, a gamepad or the mouse).  The round's rules
+// are the same as online, but nothing goes over the network.
+
+use crate::game::{GameManagerConfig, GameManagerUiConfig};
+use crate::input::InputSource;
+use crate::maps::{draw_map, MapConfig};
+use crate::painter::TextConfig;
+use crate::players::PlayerSnapshot;
+use crate::powerups::{draw_pickups, PowerUps};
+use crate::round::{draw_coins, draw_results, draw_timer, draw_zones, random_coin, GameMode, RoundFrame, RoundResults, RoundTracker,
+                   Zone};
+use crate::shrink::{draw_boundary, ArenaRect, Boundary, ShrinkSchedule};
+use crate::snake::Snake;
+use crate::traits::BaseGame;
+use crate::widgets::{Button, ButtonConfig, ButtonThinkResult};
+
+use engine_p::interpolable::Pos2d;
+use serde::{Serialize, Deserialize};
+
+use std::collections::HashMap;
+
+/// Config types
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SeatConfig {
+    pub name: String,
+    pub style: String, // stroke style of the seat's snake
+    pub start_point: Pos2d,
+    pub input: InputSource,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HotSeatConfig {
+    pub seats: Vec<SeatConfig>, // the first 'min_players' always play
+    pub min_players: usize,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct HotSeatUiConfig {
+    pub score_text: TextConfig, // for the first seat, the next ones go 'score_line_height' lower
+    pub score_line_height: f64,
+    pub players_button: ButtonConfig, // adds a seat, or goes back to 'min_players'
+    pub restart_button: ButtonConfig,
+    pub exit_button: ButtonConfig,
+}
+
+/// HotSeatManager
+pub struct HotSeatManager {
+    snakes: Vec<Snake>, // one per seat playing, the seat's index being its join order
+    tracker: RoundTracker,
+    coins: Vec<Pos2d>,
+    zones: Vec<Zone>,
+    scores: HashMap<u32, u32>, // by seat
+    power_ups: PowerUps,
+    boundary: Option<Boundary>,
+    shrink: ShrinkSchedule,
+    start_time: f64,
+    round_time: f64, // seconds the round lasts at most
+    map: Option<MapConfig>,
+    results: Option<RoundResults>, // once the round is over
+
+    players_button: Button,
+    restart_button: Button,
+    exit_button: Button,
+}
+
+pub enum HotSeatManagerThinkResult {
+    Exit,
+}
+
+impl HotSeatManager {
+    pub fn new(game: &dyn BaseGame, config: &GameManagerConfig, arena: &ArenaRect) -> Self {
+        let mut mgr = HotSeatManager {
+            snakes: Vec::new(),
+            tracker: RoundTracker::new(),
+            coins: Vec::new(),
+            zones: Vec::new(),
+            scores: HashMap::new(),
+            power_ups: PowerUps::new(),
+            boundary: None,
+            shrink: ShrinkSchedule::new(),
+            start_time: 0.0,
+            round_time: 0.0,
+            map: None,
+            results: None,
+            players_button: Button::new(),
+            restart_button: Button::new(),
+            exit_button: Button::new(),
+        };
+        mgr.restart(game, config.hot_seat.min_players, config, arena);
+        mgr
+    }
+
+    /// Start a new round with the first 'player_count' seats
+    fn restart(&mut self, game: &dyn BaseGame, player_count: usize, config: &GameManagerConfig, arena: &ArenaRect) {
+        self.snakes = config.hot_seat.seats.iter().take(player_count)
+            .map(|seat| {
+                let mut snake = Snake::new_local(&seat.name, &seat.start_point);
+                snake.set_input(seat.input.clone());
+                snake.set_style(&seat.style);
+                snake
+            })
+            .collect();
+
+        self.tracker.start(&self.snapshots(), &mut self.coins, &mut self.zones, &mut self.scores,
+                           config.mode, &config.round);
+        self.power_ups.reset(&config.power_ups);
+        self.boundary = match config.mode {
+            GameMode::ShrinkingArena => Some(Boundary::new(*arena)),
+            _ => None,
+        };
+        self.shrink.reset();
+        self.start_time = game.now();
+        self.round_time = config.round.round_time;
+        self.map = config.selected_map().cloned();
+        self.results = None;
+    }
+
+    fn snapshots(&self) -> Vec<PlayerSnapshot> {
+        self.snakes.iter().enumerate()
+            .map(|(seat, snake)| PlayerSnapshot {
+                name: snake.name().to_string(),
+                join_order: seat as u32,
+                snake_points: snake.points().clone(),
+                team: None,
+            })
+            .collect()
+    }
+
+    /// Handle per-frame processing.  'arena' is the full arena, as drawn.
+    pub fn think(&mut self, game: &mut dyn BaseGame, config: &GameManagerConfig, ui_cfg: &GameManagerUiConfig,
+                 arena: &ArenaRect) -> Option<HotSeatManagerThinkResult> {
+        let hot_seat_ui = &ui_cfg.hot_seat;
+        if self.exit_button.think(game.mouse(), &hot_seat_ui.exit_button).into_iter()
+            .any(|res| matches!(res, ButtonThinkResult::Clicked)) {
+            return Some(HotSeatManagerThinkResult::Exit);
+        }
+
+        if self.restart_button.think(game.mouse(), &hot_seat_ui.restart_button).into_iter()
+            .any(|res| matches!(res, ButtonThinkResult::Clicked)) {
+            self.restart(game, self.snakes.len(), config, arena);
+        }
+
+        if self.players_button.think(game.mouse(), &hot_seat_ui.players_button).into_iter()
+            .any(|res| matches!(res, ButtonThinkResult::Clicked)) {
+            let max_players = config.hot_seat.seats.len();
+            let player_count = if self.snakes.len() >= max_players { config.hot_seat.min_players } else { self.snakes.len() + 1 };
+            self.restart(game, player_count, config, arena);
+        }
+
+        if self.results.is_some() {
+            return None;
+        }
+
+        let collected = self.tracker.coins_collected();
+        let snatched = self.tracker.state().times_snatched();
+        for (seat, snake) in self.snakes.iter_mut().enumerate() {
+            self.power_ups.apply_to_snake(seat as u32, snake, &config.power_ups, &ui_cfg.power_ups);
+            snake.set_coins_collected(collected.get(&(seat as u32)).copied().unwrap_or(0));
+            snake.set_times_snatched(snatched.get(&(seat as u32)).copied().unwrap_or(0));
+            snake.think(game, &config.player_mgr.snake, self.map.as_ref());
+        }
+
+        let round_time = game.now() - self.start_time;
+        if let Some(boundary) = &mut self.boundary {
+            boundary.think(game.elapsed_time());
+            self.shrink.think(round_time, boundary, arena, &config.shrink);
+
+            let rect = boundary.rect();
+            for snake in self.snakes.iter_mut() {
+                snake.trim(|pos| rect.contains(pos));
+            }
+        }
+
+        let snapshots = self.snapshots();
+        let elapsed = game.elapsed_time();
+        self.power_ups.think(elapsed, &snapshots, &config.power_ups, || random_coin(&config.round));
+        self.power_ups.attract(&mut self.coins, &snapshots, elapsed, &config.power_ups);
+        self.tracker.think(elapsed, RoundFrame {
+            players: &snapshots,
+            coins: &mut self.coins,
+            zones: &mut self.zones,
+            scores: &mut self.scores,
+            power_ups: &self.power_ups,
+        }, &config.round, &config.player_mgr.snake);
+        if let Some(boundary) = &self.boundary {
+            let rect = boundary.rect();
+            self.tracker.eliminate_outside(&snapshots, |pos| rect.contains(pos));
+        }
+
+        let time_up = round_time >= self.round_time;
+        self.results = self.tracker.results(&snapshots, &self.scores, time_up, &config.round, &Vec::new());
+
+        None
+    }
+
+    pub fn draw(&self, game: &dyn BaseGame, ui_cfg: &GameManagerUiConfig) {
+        if let Some(map) = &self.map {
+            draw_map(game, map, &ui_cfg.map);
+        }
+        draw_zones(game, &self.zones, &ui_cfg.round);
+        draw_coins(game, &self.coins, &ui_cfg.round);
+        draw_pickups(game, &self.power_ups, &ui_cfg.power_ups);
+        if let Some(boundary) = &self.boundary {
+            draw_boundary(game, boundary, &ui_cfg.shrink);
+        }
+        for snake in self.snakes.iter() {
+            snake.draw(game, self.map.as_ref());
+            snake.draw_meters(game, self.map.as_ref(), &ui_cfg.snake);
+        }
+
+        match &self.results {
+            Some(results) => draw_results(game, results, &ui_cfg.round),
+            None => draw_timer(game, (self.round_time - (game.now() - self.start_time)).max(0.0), &ui_cfg.round),
+        }
+
+        let hot_seat_ui = &ui_cfg.hot_seat;
+        let painter = game.painter();
+        for (seat, snake) in self.snakes.iter().enumerate() {
+            let text = format!("{}: {}", snake.name(), self.scores.get(&(seat as u32)).copied().unwrap_or(0));
+            painter.draw_text(&text, &(0.0, seat as f64 * hot_seat_ui.score_line_height).into(), 1000.0,
+                              &TextConfig {
+                                  style: snake.style().to_string(),
+                                  ..hot_seat_ui.score_text.clone()
+                              });
+        }
+
+        let mouse = game.mouse();
+        self.players_button.draw(mouse, painter, &hot_seat_ui.players_button);
+        self.restart_button.draw(mouse, painter, &hot_seat_ui.restart_button);
+        self.exit_button.draw(mouse, painter, &hot_seat_ui.exit_button);
+    }
+}
@@ -0,0 +1,163 @@
+// Input other than the mouse, for several local snakes sharing one device: held keys,
+// every touch on the canvas, and gamepads.  Each local snake is bound to one
+// 'InputSource', which says where it wants to grow.
+
+use engine_p::interpolable::Pos2d;
+use serde::{Serialize, Deserialize};
+use wasm_bindgen::prelude::*;
+use web_sys::{Gamepad, HtmlCanvasElement, KeyboardEvent, TouchEvent};
+
+use crate::mouse::MouseManager;
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+// How far ahead of the snake's head a direction (from keys or a stick) aims
+const DIRECTION_REACH: f64 = 100.0;
+
+// Stick deflection below which a gamepad is considered idle
+const STICK_DEAD_ZONE: f64 = 0.25;
+
+/// Config types
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub enum InputSource {
+    // Grows toward the mouse (or the first touch) while it's down
+    Mouse,
+
+    // Grows in the direction of the held keys, named by 'KeyboardEvent.code', e.g. "KeyW"
+    Keys { up: String, down: String, left: String, right: String },
+
+    // Grows toward the first touch inside this rectangle of the arena
+    TouchRegion { pos: Pos2d, width: f64, height: f64 },
+
+    // Grows in the direction of the left stick of the gamepad with this index
+    Gamepad(u32),
+}
+
+struct InputManagerImp {
+    canvas: HtmlCanvasElement,
+    real_width: f64,
+    real_height: f64,
+    keys: HashSet<String>, // codes of the keys held down
+    touches: Vec<Pos2d>, // every touch on the canvas
+}
+
+impl InputManagerImp {
+    fn handle_touches(&mut self, evt: &TouchEvent) {
+        // Adjust touch x and y for offscreen canvas coordinates, as the mouse does
+        let width_factor = self.real_width / self.canvas.width() as f64;
+        let height_factor = self.real_height / self.canvas.height() as f64;
+        let rect = self.canvas.get_bounding_client_rect();
+
+        let touches = evt.touches();
+        self.touches = (0..touches.length())
+            .filter_map(|idx| touches.item(idx))
+            .map(|touch| ((touch.client_x() as f64 - rect.left()) * width_factor,
+                          (touch.client_y() as f64 - rect.top()) * height_factor).into())
+            .collect();
+    }
+}
+
+pub struct InputManager {
+    imp: Rc<RefCell<InputManagerImp>>,
+    _on_keydown_closure: Closure::<dyn FnMut(KeyboardEvent)>,
+    _on_keyup_closure: Closure::<dyn FnMut(KeyboardEvent)>,
+    _on_touch_closure: Closure::<dyn FnMut(TouchEvent)>,
+}
+
+impl InputManager {
+    // PUBLIC
+    pub fn new(canvas: HtmlCanvasElement, real_width: f64, real_height: f64) -> Self {
+        let imp = Rc::new(RefCell::new(InputManagerImp {
+            canvas: canvas.clone(),
+            real_width,
+            real_height,
+            keys: HashSet::new(),
+            touches: Vec::new(),
+        }));
+
+        let document = web_sys::window().expect("window").document().expect("document");
+
+        // Key down
+        let mut imp_ref = imp.clone();
+        let key_down_closure = Closure::<dyn FnMut(KeyboardEvent)>::new(move |evt: KeyboardEvent| {
+            (*imp_ref).borrow_mut().keys.insert(evt.code());
+        });
+        document.add_event_listener_with_callback("keydown", key_down_closure.as_ref().unchecked_ref())
+            .expect("keydown");
+
+        // Key up
+        imp_ref = imp.clone();
+        let key_up_closure = Closure::<dyn FnMut(KeyboardEvent)>::new(move |evt: KeyboardEvent| {
+            (*imp_ref).borrow_mut().keys.remove(&evt.code());
+        });
+        document.add_event_listener_with_callback("keyup", key_up_closure.as_ref().unchecked_ref())
+            .expect("keyup");
+
+        // Every touch event leaves us with the touches still on the canvas
+        imp_ref = imp.clone();
+        let touch_closure = Closure::<dyn FnMut(TouchEvent)>::new(move |evt: TouchEvent| {
+            (*imp_ref).borrow_mut().handle_touches(&evt);
+        });
+        for event in ["touchstart", "touchmove", "touchend", "touchcancel"] {
+            canvas.add_event_listener_with_callback(event, touch_closure.as_ref().unchecked_ref())
+                .expect(event);
+        }
+
+        Self {
+            imp,
+            _on_keydown_closure: key_down_closure,
+            _on_keyup_closure: key_up_closure,
+            _on_touch_closure: touch_closure,
+        }
+    }
+
+    pub fn is_key_down(&self, code: &str) -> bool {
+        (*self.imp).borrow().keys.contains(code)
+    }
+
+    /// Return the first touch inside the rectangle defined by the specified upper-left
+    /// 'pos', and 'width' and 'height', if any
+    pub fn touch_in_rect(&self, pos: &Pos2d, width: f64, height: f64) -> Option<Pos2d> {
+        (*self.imp).borrow().touches.iter()
+            .find(|touch| touch.x > pos.x && touch.x < (pos.x + width) &&
+                          touch.y > pos.y && touch.y < (pos.y + height))
+            .copied()
+    }
+
+    /// Return the (x, y) deflection of the left stick of the gamepad with the specified
+    /// 'index', if it's connected and not idle
+    pub fn gamepad_stick(&self, index: u32) -> Option<(f64, f64)> {
+        let gamepads = web_sys::window()?.navigator().get_gamepads().ok()?;
+        let gamepad = gamepads.get(index).dyn_into::<Gamepad>().ok()?;
+        let axes = gamepad.axes();
+        let x = axes.get(0).as_f64()?;
+        let y = axes.get(1).as_f64()?;
+
+        if x.hypot(y) < STICK_DEAD_ZONE { None } else { Some((x, y)) }
+    }
+
+    /// Return where a snake whose head is at 'head' and that's controlled by 'source'
+    /// should grow toward, or None if it should shrink back
+    pub fn target(&self, source: &InputSource, head: Pos2d, mouse: &MouseManager) -> Option<Pos2d> {
+        let direction = match source {
+            InputSource::Mouse => return if mouse.is_down() { Some(mouse.pos()) } else { None },
+            InputSource::TouchRegion { pos, width, height } => return self.touch_in_rect(pos, *width, *height),
+            InputSource::Keys { up, down, left, right } => {
+                let axis = |neg: &str, pos: &str| {
+                    (self.is_key_down(pos) as i32 - self.is_key_down(neg) as i32) as f64
+                };
+                (axis(left, right), axis(up, down))
+            }
+            InputSource::Gamepad(index) => self.gamepad_stick(*index)?,
+        };
+
+        let len = direction.0.hypot(direction.1);
+        if len == 0.0 {
+            return None;
+        }
+
+        Some((head.x + direction.0 / len * DIRECTION_REACH, head.y + direction.1 / len * DIRECTION_REACH).into())
+    }
+}
@@ -1,23 +1,44 @@
 mod game;
+mod hotseat;
+mod input;
+mod maps;
 mod mouse;
 mod network;
 mod painter;
+mod placement;
 mod players;
+mod powerups;
+mod practice;
+mod replay;
+mod round;
+mod series;
+mod shrink;
 mod snake;
 mod traits;
 mod utils;
 mod widgets;
 
 use engine_p::interpolable::{Pos2d};
+use input::InputManager;
+use maps::{MapConfig, MapUiConfig, Portal, Wall};
 use mouse::MouseManager;
-use network::NetworkManager;
-use painter::{Painter, TextConfig};
-use game::{GameManager, GameManagerConfig, GameManagerUiConfig, MainMenuUiConfig};
-use players::PlayerManagerConfig;
+use network::{NetLogLevel, NetSimConfig, NetworkManager};
+use painter::{Painter, ProgressBarConfig, ShapeConfig, TextConfig};
+use placement::PlacementUiConfig;
+use hotseat::{HotSeatConfig, HotSeatUiConfig, SeatConfig};
+use input::InputSource;
+use game::{GameManager, GameManagerConfig, GameManagerUiConfig, MainMenuUiConfig, SavedGame};
+use players::{PlayerManagerConfig, StartAssignment, StartLayout};
+use powerups::{PowerUpConfig, PowerUpKind, PowerUpSpawn, PowerUpUiConfig};
+use practice::{PracticeConfig, PracticeUiConfig};
+use replay::{Replay, ReplayConfig, ReplayUiConfig};
+use round::{GameMode, RoundConfig, RoundUiConfig, WinCondition};
+use series::{SeriesConfig, SeriesMode, SeriesUiConfig};
+use shrink::{ArenaRect, ShrinkConfig, ShrinkStage, ShrinkUiConfig};
 use serde::{Serialize,Deserialize};
-use snake::{SnakeConfig};
+use snake::{BotConfig, SnakeConfig, SnakeUiConfig, StaminaConfig};
 use traits::{BaseGame, NetMsg};
-use utils::{log, set_panic_hook};
+use utils::{log, set_panic_hook, to_json_value};
 use wasm_bindgen::prelude::*;
 use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, OffscreenCanvas, OffscreenCanvasRenderingContext2d};
 use web_time::Instant;
@@ -27,9 +48,17 @@ use std::cell::RefCell;
 use crate::painter::BackgroundConfig;
 use crate::widgets::{ButtonConfig};
 
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NetStatsUiConfig {
+    pub show: bool,
+    pub text: TextConfig,
+    pub line_height: f64,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct UiConfig {
     pub fps: TextConfig,
+    pub net_stats: NetStatsUiConfig,
     pub arena_color: String,
     pub arena_pos: Pos2d,
     pub arena_width: f64,
@@ -37,9 +66,23 @@ pub struct UiConfig {
     pub game_manager: GameManagerUiConfig,
 }
 
+impl UiConfig {
+    fn arena(&self) -> ArenaRect {
+        ArenaRect {
+            pos: self.arena_pos,
+            width: self.arena_width,
+            height: self.arena_height,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct GameConfig {
     pub game_manager: GameManagerConfig,
+    pub net_log_level: NetLogLevel,
+    pub net_sim: NetSimConfig,
+    #[serde(default)]
+    pub net_sim_handles: Vec<(i32, NetSimConfig)>, // overrides 'net_sim' for these handles, as shown by the overlay
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -54,6 +97,7 @@ struct GameImp {
     network: NetworkManager<NetMsg>,
     config: OuterConfig,
     mouse: MouseManager,
+    input: InputManager,
     elapsed_time: f64,  // seconds since previous frame start (for calculating current frame)
     now: f64,
 }
@@ -75,6 +119,10 @@ impl BaseGame for GameImp {
         &self.mouse
     }
 
+    fn input(&self) -> &InputManager {
+        &self.input
+    }
+
     fn now(&self) -> f64 {
         self.now
     }
@@ -83,10 +131,12 @@ impl BaseGame for GameImp {
 impl GameImp {
     fn think(&mut self) {
         self.painter.think(self.elapsed_time);
+        self.network.think();
     }
 
     fn post_think(&mut self) {
         self.mouse.post_think();
+        self.network.flush();
     }
 }
 
@@ -123,7 +173,7 @@ impl GameState {
         let config = self.imp.config.clone();
 
         self.imp.think();
-        self.game_manager.think(&mut self.imp, &config.game.game_manager, &config.ui.game_manager);
+        self.game_manager.think(&mut self.imp, &config.game.game_manager, &config.ui.game_manager, &config.ui.arena());
     }
 
     fn post_think(&mut self) {
@@ -139,14 +189,19 @@ impl GameState {
         let cfg = &self.imp.config.ui;
 
         // Draw the game area
+        let camera = self.game_manager.camera();
         canvas.set_fill_style_str(&cfg.arena_color);
-        canvas.fill_rect(cfg.arena_pos.x, cfg.arena_pos.y, cfg.arena_width, cfg.arena_height);
+        canvas.fill_rect(cfg.arena_pos.x - camera.x, cfg.arena_pos.y - camera.y, cfg.arena_width, cfg.arena_height);
 
         self.game_manager.draw(&self.imp, &cfg.game_manager);
 
         // Draw FPS
         self.imp.painter().draw_text(&self.fps_str, &(2000, 10).into(), 300.0, &self.imp.config.ui.fps);
 
+        if cfg.net_stats.show {
+            self.draw_net_stats(&cfg.net_stats);
+        }
+
         let screen_context = self.screen_canvas
         .get_context("2d").unwrap().unwrap()
         .dyn_into::<CanvasRenderingContext2d>().unwrap();
@@ -160,8 +215,42 @@ impl GameState {
         .expect("draw offscreen canvas");
     }
 
+    // Draw the network statistics of every handle, below the FPS
+    fn draw_net_stats(&self, cfg: &NetStatsUiConfig) {
+        let mut lines: Vec<String> = Vec::new();
+        for (handle, stats) in self.imp.network.stats().iter() {
+            let total = stats.total();
+            let rtt = match stats.rtt {
+                Some(rtt) => format!("{:.0} ms", rtt * 1000.0),
+                None => "?".to_string(),
+            };
+            // Bytes are only counted while the stats are shown
+            let bytes = |count: u64| if stats.bytes_measured { format!("{} B", count) } else { "n/a".to_string() };
+            lines.push(format!("Net {}: rtt {}, queue {}, in {} ({}), out {} ({}), full upd {}",
+                               handle, rtt, stats.queue_depth, total.msgs_in, bytes(total.bytes_in),
+                               total.msgs_out, bytes(total.bytes_out), total.full_update_reqs));
+
+            let mut stream_ids: Vec<&i32> = stats.streams.keys().collect();
+            stream_ids.sort();
+            for stream_id in stream_ids {
+                let stream = &stats.streams[stream_id];
+                lines.push(format!("  {}: in {} ({}), out {} ({}), full upd {}",
+                                   stream_id, stream.msgs_in, bytes(stream.bytes_in),
+                                   stream.msgs_out, bytes(stream.bytes_out), stream.full_update_reqs));
+            }
+        }
+
+        for (idx, line) in lines.iter().enumerate() {
+            let pos: Pos2d = (2000.0, 50.0 + idx as f64 * cfg.line_height).into();
+            self.imp.painter().draw_text(line, &pos, 560.0, &cfg.text);
+        }
+    }
+
     fn update_config(&mut self, cfg: &OuterConfig) {
         self.imp.config = cfg.clone();
+        self.imp.network.set_log_level(cfg.game.net_log_level);
+        self.imp.network.set_measure_sizes(cfg.ui.net_stats.show);
+        self.imp.network.set_sim_config(&cfg.game.net_sim, &cfg.game.net_sim_handles);
         //self.imp.painter.update_config(&cfg.ui.images);
     }
 
@@ -183,12 +272,18 @@ pub fn init_state(config: JsValue, canvas: JsValue, _images: JsValue, _audio_ctx
 
     let painter = Painter::new(offscreen_context);
 
+    let mut network = NetworkManager::new();
+    network.set_log_level(game_config.game.net_log_level);
+    network.set_measure_sizes(game_config.ui.net_stats.show);
+    network.set_sim_config(&game_config.game.net_sim, &game_config.game.net_sim_handles);
+
     let game_imp = GameImp {
         painter: painter,
-        network: NetworkManager::new(),
+        network,
         config: game_config,
         elapsed_time: 0.0,
         mouse: MouseManager::new(screen_canvas.clone(), 2560.0, 1440.0),
+        input: InputManager::new(screen_canvas.clone(), 2560.0, 1440.0),
         now: 0.0,
     };
 
@@ -251,6 +346,38 @@ pub fn build_default_config() -> OuterConfig {
         }
     }
 
+    fn start_marker(style: &str) -> BackgroundConfig {
+        BackgroundConfig {
+            offset: (-40, -40).into(),
+            width: 80.0,
+            height: 80.0,
+            corner_radius: 40.0,
+            border_style: "black".to_string(),
+            border_alpha: 0.8,
+            border_width: 4.0,
+            bg_style: style.to_string(),
+            bg_alpha: 0.6,
+        }
+    }
+
+    fn snake_meter(y: f64, style: &str) -> ProgressBarConfig {
+        ProgressBarConfig {
+            bg: BackgroundConfig {
+                offset: (-30.0, y).into(),
+                width: 60.0,
+                height: 8.0,
+                corner_radius: 3.0,
+                border_style: "black".to_string(),
+                border_alpha: 0.6,
+                border_width: 1.0,
+                bg_style: "white".to_string(),
+                bg_alpha: 0.5,
+            },
+            done_style: style.to_string(),
+            done_alpha: 0.8,
+        }
+    }
+
     fn default_button(x: f64, y: f64, text: &str) -> ButtonConfig {
         ButtonConfig {
             bg_normal: BackgroundConfig {
@@ -289,6 +416,20 @@ pub fn build_default_config() -> OuterConfig {
                 alpha: 0.7,
                 is_command: false,
             },
+            net_stats: NetStatsUiConfig {
+                show: false,
+                text: TextConfig {
+                    offset: (0, 0).into(),
+                    stroke: false,
+                    style: "black".to_string(),
+                    font: "comic sans".to_string(),
+                    size: 20,
+                    center_and_fit: false,
+                    alpha: 0.7,
+                    is_command: false,
+                },
+                line_height: 24.0,
+            },
             arena_color: "pink".to_string(),
             arena_pos: (200,200).into(),
             arena_width: 1000.0,
@@ -304,23 +445,440 @@ pub fn build_default_config() -> OuterConfig {
                     alpha: 0.7,
                     is_command: false,
                 },
+                player_names_line_height: 40.0,
+                status_text: TextConfig {
+                    offset: (400, 1250).into(),
+                    stroke: false,
+                    style: "black".to_string(),
+                    font: "comic sans".to_string(),
+                    size: 40,
+                    center_and_fit: false,
+                    alpha: 0.9,
+                    is_command: false,
+                },
+                play_button: default_button(1500.0, 1200.0, "Play"),
+                add_bot_button: default_button(1500.0, 800.0, "Add Bot"),
+                start_button: default_button(1500.0, 1000.0, "Start"),
+                placement: PlacementUiConfig {
+                    open_marker: start_marker("white"),
+                    mine_marker: start_marker("green"),
+                    claimed_marker: start_marker("orange"),
+                    contested_marker: start_marker("red"),
+                    taken_marker: BackgroundConfig {
+                        bg_alpha: 0.2,
+                        ..start_marker("black")
+                    },
+                    countdown_text: TextConfig {
+                        offset: (200, 60).into(),
+                        stroke: false,
+                        style: "black".to_string(),
+                        font: "comic sans".to_string(),
+                        size: 40,
+                        center_and_fit: false,
+                        alpha: 0.9,
+                        is_command: false,
+                    },
+                },
+                round: RoundUiConfig {
+                    coin: BackgroundConfig {
+                        offset: (-15, -15).into(),
+                        width: 30.0,
+                        height: 30.0,
+                        corner_radius: 15.0,
+                        border_style: "darkgoldenrod".to_string(),
+                        border_alpha: 1.0,
+                        border_width: 3.0,
+                        bg_style: "gold".to_string(),
+                        bg_alpha: 1.0,
+                    },
+                    zone: BackgroundConfig {
+                        offset: (-1, -1).into(),
+                        width: 2.0,
+                        height: 2.0,
+                        corner_radius: 1.0,
+                        border_style: "seagreen".to_string(),
+                        border_alpha: 0.8,
+                        border_width: 4.0,
+                        bg_style: "palegreen".to_string(),
+                        bg_alpha: 0.4,
+                    },
+                    timer_text: TextConfig {
+                        offset: (1100, 60).into(),
+                        stroke: false,
+                        style: "black".to_string(),
+                        font: "comic sans".to_string(),
+                        size: 40,
+                        center_and_fit: false,
+                        alpha: 0.9,
+                        is_command: false,
+                    },
+                    results_title: TextConfig {
+                        offset: (300, 300).into(),
+                        stroke: false,
+                        style: "black".to_string(),
+                        font: "comic sans".to_string(),
+                        size: 72,
+                        center_and_fit: false,
+                        alpha: 0.9,
+                        is_command: false,
+                    },
+                    results_text: TextConfig {
+                        offset: (300, 420).into(),
+                        stroke: false,
+                        style: "black".to_string(),
+                        font: "comic sans".to_string(),
+                        size: 36,
+                        center_and_fit: false,
+                        alpha: 0.9,
+                        is_command: false,
+                    },
+                    results_line_height: 50.0,
+                    rematch_button: default_button(1500.0, 1000.0, "Rematch"),
+                },
+                power_ups: PowerUpUiConfig {
+                    pickup: BackgroundConfig {
+                        offset: (-18, -18).into(),
+                        width: 36.0,
+                        height: 36.0,
+                        corner_radius: 8.0,
+                        border_style: "black".to_string(),
+                        border_alpha: 0.8,
+                        border_width: 3.0,
+                        bg_style: "white".to_string(),
+                        bg_alpha: 0.9,
+                    },
+                    speed_boost_style: "orange".to_string(),
+                    shield_style: "deepskyblue".to_string(),
+                    magnet_style: "crimson".to_string(),
+                    ghost_style: "lightgray".to_string(),
+                },
+                snake: SnakeUiConfig {
+                    length_bar: snake_meter(-44.0, "goldenrod"),
+                    stamina_bar: snake_meter(-32.0, "seagreen"),
+                },
+                shrink: ShrinkUiConfig {
+                    boundary: BackgroundConfig {
+                        offset: (0, 0).into(),
+                        width: 0.0,
+                        height: 0.0,
+                        corner_radius: 0.0,
+                        border_style: "crimson".to_string(),
+                        border_alpha: 0.9,
+                        border_width: 6.0,
+                        bg_style: "white".to_string(),
+                        bg_alpha: 0.0,
+                    },
+                },
+                map: MapUiConfig {
+                    wall: ShapeConfig {
+                        stroke: true,
+                        style: "dimgray".to_string(),
+                        alpha: 1.0,
+                    },
+                    polygon: ShapeConfig {
+                        stroke: false,
+                        style: "slategray".to_string(),
+                        alpha: 0.9,
+                    },
+                    portal_entry: BackgroundConfig {
+                        offset: (-1, -1).into(),
+                        width: 2.0,
+                        height: 2.0,
+                        corner_radius: 1.0,
+                        border_style: "indigo".to_string(),
+                        border_alpha: 0.9,
+                        border_width: 4.0,
+                        bg_style: "darkviolet".to_string(),
+                        bg_alpha: 0.6,
+                    },
+                    portal_exit: BackgroundConfig {
+                        offset: (-1, -1).into(),
+                        width: 2.0,
+                        height: 2.0,
+                        corner_radius: 1.0,
+                        border_style: "darkviolet".to_string(),
+                        border_alpha: 0.9,
+                        border_width: 4.0,
+                        bg_style: "violet".to_string(),
+                        bg_alpha: 0.3,
+                    },
+                },
+                series: SeriesUiConfig {
+                    standings_title: TextConfig {
+                        offset: (1300, 300).into(),
+                        stroke: false,
+                        style: "black".to_string(),
+                        font: "comic sans".to_string(),
+                        size: 48,
+                        center_and_fit: false,
+                        alpha: 0.9,
+                        is_command: false,
+                    },
+                    standings_text: TextConfig {
+                        offset: (1300, 380).into(),
+                        stroke: false,
+                        style: "black".to_string(),
+                        font: "comic sans".to_string(),
+                        size: 32,
+                        center_and_fit: false,
+                        alpha: 0.9,
+                        is_command: false,
+                    },
+                    standings_line_height: 44.0,
+                    next_round_button: default_button(1500.0, 1000.0, "Next Round"),
+                },
+                practice: PracticeUiConfig {
+                    info_text: TextConfig {
+                        offset: (200, 100).into(),
+                        stroke: false,
+                        style: "black".to_string(),
+                        font: "comic sans".to_string(),
+                        size: 40,
+                        center_and_fit: false,
+                        alpha: 0.9,
+                        is_command: false,
+                    },
+                    bots_button: default_button(1500.0, 200.0, "Bots"),
+                    restart_button: default_button(1500.0, 350.0, "Restart"),
+                    exit_button: default_button(1500.0, 500.0, "Exit"),
+                },
+                hot_seat: HotSeatUiConfig {
+                    score_text: TextConfig {
+                        offset: (1500, 650).into(),
+                        stroke: false,
+                        style: "black".to_string(),
+                        font: "comic sans".to_string(),
+                        size: 40,
+                        center_and_fit: false,
+                        alpha: 0.9,
+                        is_command: false,
+                    },
+                    score_line_height: 50.0,
+                    players_button: default_button(1500.0, 200.0, "Players"),
+                    restart_button: default_button(1500.0, 350.0, "Restart"),
+                    exit_button: default_button(1500.0, 500.0, "Exit"),
+                },
+                replay: ReplayUiConfig {
+                    pause_button: default_button(1500.0, 200.0, "Pause"),
+                    slower_button: default_button(1500.0, 350.0, "Slower"),
+                    faster_button: default_button(1500.0, 500.0, "Faster"),
+                    follow_button: default_button(1500.0, 650.0, "Follow"),
+                    exit_button: default_button(1500.0, 800.0, "Exit"),
+                    seek_bar: BackgroundConfig {
+                        offset: (200, 1300).into(),
+                        width: 1000.0,
+                        height: 40.0,
+                        ..button_bg()
+                    },
+                    seek_done: BackgroundConfig {
+                        offset: (200, 1300).into(),
+                        width: 1000.0,
+                        height: 40.0,
+                        bg_style: "black".to_string(),
+                        bg_alpha: 0.6,
+                        ..button_bg()
+                    },
+                    info_text: TextConfig {
+                        offset: (200, 1360).into(),
+                        stroke: false,
+                        style: "black".to_string(),
+                        font: "comic sans".to_string(),
+                        size: 30,
+                        center_and_fit: false,
+                        alpha: 0.9,
+                        is_command: false,
+                    },
+                    follow_center: (700, 700).into(),
+                    score_text: TextConfig {
+                        offset: (1500, 1000).into(),
+                        stroke: false,
+                        style: "black".to_string(),
+                        font: "comic sans".to_string(),
+                        size: 40,
+                        center_and_fit: false,
+                        alpha: 0.9,
+                        is_command: false,
+                    },
+                    score_line_height: 50.0,
+                },
                 main_menu: MainMenuUiConfig {
                     host_button: default_button(400.0, 400.0, "Host Game"),
                     join_button: default_button(400.0, 700.0, "Join Game"),
+                    spectate_button: default_button(400.0, 1000.0, "Spectate"),
+                    practice_button: default_button(1000.0, 400.0, "Practice"),
+                    hot_seat_button: default_button(1000.0, 700.0, "Hot Seat"),
                 }
             }
         },
         game: GameConfig {
             game_manager: GameManagerConfig {
                 player_mgr: PlayerManagerConfig {
-                    snake_start_points: vec![
-                        (200, 200).into(), (600, 200).into(), (200, 600).into(), (600, 600).into()
+                    start_layouts: vec![
+                        StartLayout {
+                            name: "Duel".to_string(),
+                            max_players: 2,
+                            points: vec![(300, 700).into(), (1100, 700).into()],
+                        },
+                        StartLayout {
+                            name: "Square".to_string(),
+                            max_players: 4,
+                            points: vec![
+                                (200, 200).into(), (600, 200).into(), (200, 600).into(), (600, 600).into()
+                            ],
+                        },
+                        StartLayout {
+                            name: "Ring".to_string(),
+                            max_players: 8,
+                            points: vec![
+                                (700, 300).into(), (983, 417).into(), (1100, 700).into(), (983, 983).into(),
+                                (700, 1100).into(), (417, 983).into(), (300, 700).into(), (417, 417).into()
+                            ],
+                        },
                     ],
+                    start_layout: "".to_string(),
+                    start_assignment: StartAssignment::Fair,
+                    teams: Vec::new(),
                     snake: SnakeConfig {
                         grow_speed: 100.0,
+                        start_length: 600.0,
+                        length_per_coin: 150.0,
+                        length_per_snatch: 200.0,
+                        stamina: Some(StaminaConfig {
+                            capacity: 4.0,
+                            drain_rate: 1.0,
+                            regen_rate: 1.5,
+                        }),
+                        bot: BotConfig {
+                            reaction_time: 0.5,
+                            aim_error: 30.0,
+                            speed_factor: 0.8,
+                            max_points: 40,
+                        },
                     },
-                }
-            }
+                    reconnect_grace: 30.0,
+                },
+                connect_timeout: 10.0,
+                handshake_timeout: 5.0,
+                reconnect_attempts: 5,
+                reconnect_backoff: 1.0,
+                reconnect_backoff_max: 8.0,
+                ping_interval: 1.0,
+                placement_time: 10.0,
+                mode: GameMode::Coins,
+                round: RoundConfig {
+                    win_condition: WinCondition::ScoreTarget(10),
+                    round_time: 120.0,
+                    coin_count: 5,
+                    coin_radius: 30.0,
+                    snatch_radius: 15.0,
+                    coin_area_pos: (250, 250).into(),
+                    coin_area_width: 900.0,
+                    coin_area_height: 900.0,
+                    zone_count: 2,
+                    zone_radius: 100.0,
+                    zone_move_interval: 20.0,
+                    zone_points_per_second: 1.0,
+                },
+                power_ups: PowerUpConfig {
+                    spawns: vec![
+                        PowerUpSpawn { kind: PowerUpKind::SpeedBoost, weight: 3.0, duration: 5.0 },
+                        PowerUpSpawn { kind: PowerUpKind::Shield, weight: 2.0, duration: 8.0 },
+                        PowerUpSpawn { kind: PowerUpKind::Magnet, weight: 2.0, duration: 8.0 },
+                        PowerUpSpawn { kind: PowerUpKind::Ghost, weight: 1.0, duration: 5.0 },
+                    ],
+                    spawn_interval: 10.0,
+                    max_pickups: 2,
+                    pickup_radius: 25.0,
+                    speed_boost_factor: 1.75,
+                    magnet_radius: 200.0,
+                    magnet_speed: 150.0,
+                },
+                shrink: ShrinkConfig {
+                    stages: vec![
+                        ShrinkStage { start: 20.0, duration: 20.0, scale: 0.75 },
+                        ShrinkStage { start: 50.0, duration: 20.0, scale: 0.5 },
+                        ShrinkStage { start: 80.0, duration: 30.0, scale: 0.2 },
+                    ],
+                },
+                maps: vec![
+                    MapConfig {
+                        name: "Crossroads".to_string(),
+                        walls: vec![
+                            Wall { from: (700, 450).into(), to: (700, 550).into(), thickness: 20.0 },
+                            Wall { from: (700, 850).into(), to: (700, 950).into(), thickness: 20.0 },
+                        ],
+                        polygons: vec![
+                            vec![(700, 620).into(), (780, 700).into(), (700, 780).into(), (620, 700).into()],
+                        ],
+                        portals: vec![
+                            Portal { entry: (450, 1050).into(), exit: (950, 350).into(), radius: 30.0 },
+                            Portal { entry: (950, 1050).into(), exit: (450, 350).into(), radius: 30.0 },
+                        ],
+                    },
+                ],
+                map: Some(0),
+                series: SeriesConfig {
+                    mode: SeriesMode::Single,
+                    bracket_match_size: 2,
+                },
+                practice: PracticeConfig {
+                    coin_target: 10,
+                    start_points: vec![(700, 700).into(), (300, 300).into(), (1100, 1100).into(), (1100, 300).into()],
+                },
+                hot_seat: HotSeatConfig {
+                    seats: vec![
+                        SeatConfig {
+                            name: "Player 1".to_string(),
+                            style: "blue".to_string(),
+                            start_point: (300, 700).into(),
+                            input: InputSource::Keys {
+                                up: "KeyW".to_string(),
+                                down: "KeyS".to_string(),
+                                left: "KeyA".to_string(),
+                                right: "KeyD".to_string(),
+                            },
+                        },
+                        SeatConfig {
+                            name: "Player 2".to_string(),
+                            style: "red".to_string(),
+                            start_point: (1100, 700).into(),
+                            input: InputSource::Keys {
+                                up: "ArrowUp".to_string(),
+                                down: "ArrowDown".to_string(),
+                                left: "ArrowLeft".to_string(),
+                                right: "ArrowRight".to_string(),
+                            },
+                        },
+                        SeatConfig {
+                            name: "Player 3".to_string(),
+                            style: "green".to_string(),
+                            start_point: (700, 300).into(),
+                            input: InputSource::Gamepad(0),
+                        },
+                        SeatConfig {
+                            name: "Player 4".to_string(),
+                            style: "purple".to_string(),
+                            start_point: (700, 1100).into(),
+                            input: InputSource::Gamepad(1),
+                        },
+                    ],
+                    min_players: 2,
+                },
+                replay: ReplayConfig {
+                    record_interval: 0.1,
+                    max_frames: 6000, // 10 minutes
+                },
+            },
+            net_log_level: NetLogLevel::Info,
+            net_sim: NetSimConfig {
+                enabled: false,
+                latency: 0.1,
+                jitter: 0.05,
+                drop_rate: 0.0,
+                dup_rate: 0.0,
+                reorder_rate: 0.0,
+            },
+            net_sim_handles: Vec::new(),
         }
     }
 }
@@ -330,6 +888,77 @@ pub fn default_config() -> JsValue {
     serde_wasm_bindgen::to_value(&build_default_config()).unwrap()
 }
 
+/// Return what the game at index 'state' recorded of its current match, to save as a
+/// file, or 'undefined' if it's not in a match
+#[wasm_bindgen]
+pub fn export_replay(state: usize) -> JsValue {
+    unsafe {
+        #[allow(static_mut_refs)]
+        match S_STATES.borrow().get(state).and_then(|s| s.game_manager.replay()) {
+            Some(replay) => to_json_value(replay),
+            None => JsValue::UNDEFINED,
+        }
+    }
+}
+
+/// Make the game at index 'state' play back the specified 'replay', previously returned
+/// by 'export_replay'.  Return false if it can't.
+#[wasm_bindgen]
+pub fn load_replay(state: usize, replay: JsValue) -> bool {
+    let replay = match serde_wasm_bindgen::from_value::<Replay>(replay) {
+        Ok(replay) => replay,
+        Err(e) => {
+            log(&format!("Failed parsing replay: {}", e));
+            return false;
+        }
+    };
+
+    unsafe {
+        #[allow(static_mut_refs)]
+        match S_STATES.borrow_mut().get_mut(state) {
+            Some(s) => s.game_manager.view_replay(replay),
+            None => false,
+        }
+    }
+}
+
+/// Return everything the game at index 'state' knows about the game it's hosting, to
+/// save as a file, or 'undefined' if it's not hosting one
+#[wasm_bindgen]
+pub fn export_game_state(state: usize) -> JsValue {
+    unsafe {
+        #[allow(static_mut_refs)]
+        match S_STATES.borrow().get(state).and_then(|s| s.game_manager.save_host(s.imp.now)) {
+            Some(saved) => to_json_value(&saved),
+            None => JsValue::UNDEFINED,
+        }
+    }
+}
+
+/// Make the game at index 'state' host the specified 'saved' game, previously returned
+/// by 'export_game_state'.  Return false if it can't.
+#[wasm_bindgen]
+pub fn import_game_state(state: usize, saved: JsValue) -> bool {
+    let saved = match serde_wasm_bindgen::from_value::<SavedGame>(saved) {
+        Ok(saved) => saved,
+        Err(e) => {
+            log(&format!("Failed parsing game state: {}", e));
+            return false;
+        }
+    };
+
+    unsafe {
+        #[allow(static_mut_refs)]
+        match S_STATES.borrow_mut().get_mut(state) {
+            Some(s) => {
+                let config = s.imp.config.game.game_manager.clone();
+                s.game_manager.restore_host(&mut s.imp, &config, saved)
+            }
+            None => false,
+        }
+    }
+}
+
 #[wasm_bindgen]
 pub fn update_config(config: JsValue) {
     match serde_wasm_bindgen::from_value::<OuterConfig>(config) {
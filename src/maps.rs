@@ -0,0 +1,263 @@
+// Maps: walls, blocking polygons and portals in the arena.  The host picks the map and
+// sends it to the clients when a round starts.  Snakes can't grow through obstacles,
+// and a snake's head reaching a portal's entry jumps to its exit.
+
+use crate::painter::{BackgroundConfig, ShapeConfig};
+use crate::traits::BaseGame;
+
+use engine_p::interpolable::Pos2d;
+use serde::{Serialize, Deserialize};
+
+/// Config types
+
+// A straight wall, 'thickness' wide
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Wall {
+    pub from: Pos2d,
+    pub to: Pos2d,
+    pub thickness: f64,
+}
+
+// A snake's head that gets within 'radius' of 'entry' jumps to 'exit'
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Portal {
+    pub entry: Pos2d,
+    pub exit: Pos2d,
+    pub radius: f64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct MapConfig {
+    pub name: String,
+    pub walls: Vec<Wall>,
+    pub polygons: Vec<Vec<Pos2d>>, // corners of each polygon, in order
+    pub portals: Vec<Portal>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct MapUiConfig {
+    pub wall: ShapeConfig, // drawn 'thickness' wide
+    pub polygon: ShapeConfig,
+    pub portal_entry: BackgroundConfig, // scaled to each portal, with 'offset' and 'width' for a radius of 1
+    pub portal_exit: BackgroundConfig, // same as 'portal_entry'
+}
+
+/// Helper functions
+// Return the cross product of ('b' - 'a') and ('c' - 'a'), positive if 'c' is left of
+// the line from 'a' to 'b'
+fn cross(a: Pos2d, b: Pos2d, c: Pos2d) -> f64 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+// Return true if the segments from 'a1' to 'a2' and from 'b1' to 'b2' cross
+fn segments_cross(a1: Pos2d, a2: Pos2d, b1: Pos2d, b2: Pos2d) -> bool {
+    let d1 = cross(b1, b2, a1);
+    let d2 = cross(b1, b2, a2);
+    let d3 = cross(a1, a2, b1);
+    let d4 = cross(a1, a2, b2);
+    ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0)) &&
+    ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+}
+
+// Return the distance from 'pos' to the segment from 'a' to 'b'
+fn dist_to_segment(pos: Pos2d, a: Pos2d, b: Pos2d) -> f64 {
+    let len_sq = (b.x - a.x).powi(2) + (b.y - a.y).powi(2);
+    if len_sq == 0.0 {
+        return pos.dist(a);
+    }
+
+    let t = (((pos.x - a.x) * (b.x - a.x) + (pos.y - a.y) * (b.y - a.y)) / len_sq).clamp(0.0, 1.0);
+    pos.dist((a.x + t * (b.x - a.x), a.y + t * (b.y - a.y)).into())
+}
+
+// Return the distance between the segments from 'a1' to 'a2' and from 'b1' to 'b2'
+fn segments_dist(a1: Pos2d, a2: Pos2d, b1: Pos2d, b2: Pos2d) -> f64 {
+    if segments_cross(a1, a2, b1, b2) {
+        return 0.0;
+    }
+
+    dist_to_segment(a1, b1, b2)
+        .min(dist_to_segment(a2, b1, b2))
+        .min(dist_to_segment(b1, a1, a2))
+        .min(dist_to_segment(b2, a1, a2))
+}
+
+// Return true if 'pos' is inside the polygon with the specified 'corners'
+fn polygon_contains(corners: &[Pos2d], pos: Pos2d) -> bool {
+    let mut inside = false;
+    for (idx, a) in corners.iter().enumerate() {
+        let b = corners[(idx + 1) % corners.len()];
+        if (a.y > pos.y) != (b.y > pos.y) && pos.x < a.x + (pos.y - a.y) * (b.x - a.x) / (b.y - a.y) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+impl MapConfig {
+    /// Return true if a snake can't grow from 'from' to 'to', because an obstacle is in the way
+    pub fn blocks(&self, from: Pos2d, to: Pos2d) -> bool {
+        let hits_wall = self.walls.iter().any(|wall| segments_dist(from, to, wall.from, wall.to) < wall.thickness / 2.0);
+        let hits_polygon = self.polygons.iter().any(|corners| {
+            polygon_contains(corners, to) ||
+            corners.iter().enumerate().any(|(idx, a)| segments_cross(from, to, *a, corners[(idx + 1) % corners.len()]))
+        });
+
+        hits_wall || hits_polygon
+    }
+
+    /// Return where a snake's head at 'pos' jumps to, if it's in a portal's entry
+    pub fn portal_exit(&self, pos: Pos2d) -> Option<Pos2d> {
+        self.portals.iter().find(|portal| portal.entry.dist(pos) < portal.radius).map(|portal| portal.exit)
+    }
+
+    /// Return true if going from 'from' to 'to' is a jump through a portal
+    pub fn is_jump(&self, from: Pos2d, to: Pos2d) -> bool {
+        self.portals.iter().any(|portal| portal.entry.dist(from) < portal.radius && portal.exit == to)
+    }
+
+    /// Cut the snake made of 'points' short of the first segment that goes through an
+    /// obstacle, keeping at least its start
+    pub fn clip(&self, points: &mut Vec<Pos2d>) {
+        let valid = points.windows(2)
+            .take_while(|seg| self.is_jump(seg[0], seg[1]) || !self.blocks(seg[0], seg[1]))
+            .count();
+
+        if valid + 1 < points.len() {
+            points.truncate((valid + 1).max(2));
+            if valid == 0 {
+                points[1] = points[0];
+            }
+        }
+    }
+}
+
+/// Draw the specified 'map'
+pub fn draw_map(game: &dyn BaseGame, map: &MapConfig, ui_cfg: &MapUiConfig) {
+    let painter = game.painter();
+
+    for corners in map.polygons.iter() {
+        painter.draw_polygon(corners, &ui_cfg.polygon);
+    }
+
+    for wall in map.walls.iter() {
+        painter.draw_line(&wall.from, &wall.to, wall.thickness, &ui_cfg.wall);
+    }
+
+    let draw_portal_end = |pos: &Pos2d, radius: f64, bg: &BackgroundConfig| {
+        painter.draw_area_background(pos, &BackgroundConfig {
+            offset: (bg.offset.x * radius, bg.offset.y * radius).into(),
+            width: bg.width * radius,
+            height: bg.height * radius,
+            corner_radius: bg.corner_radius * radius,
+            ..bg.clone()
+        });
+    };
+    for portal in map.portals.iter() {
+        draw_portal_end(&portal.entry, portal.radius, &ui_cfg.portal_entry);
+        draw_portal_end(&portal.exit, portal.radius, &ui_cfg.portal_exit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(x: f64, y: f64) -> Pos2d {
+        (x, y).into()
+    }
+
+    fn square(x: f64, y: f64, size: f64) -> Vec<Pos2d> {
+        vec![pos(x, y), pos(x + size, y), pos(x + size, y + size), pos(x, y + size)]
+    }
+
+    // A map with a wall along x = 0 from y = -50 to 50, a square from (100, -50) to
+    // (200, 50), and a portal from (-200, 0) to (300, 0)
+    fn map() -> MapConfig {
+        MapConfig {
+            name: "Test".to_string(),
+            walls: vec![Wall { from: pos(0.0, -50.0), to: pos(0.0, 50.0), thickness: 10.0 }],
+            polygons: vec![square(100.0, -50.0, 100.0)],
+            portals: vec![Portal { entry: pos(-200.0, 0.0), exit: pos(300.0, 0.0), radius: 20.0 }],
+        }
+    }
+
+    #[test]
+    fn segments_cross_only_when_they_straddle() {
+        assert!(segments_cross(pos(-1.0, 0.0), pos(1.0, 0.0), pos(0.0, -1.0), pos(0.0, 1.0)));
+        assert!(!segments_cross(pos(-1.0, 0.0), pos(1.0, 0.0), pos(0.0, 1.0), pos(0.0, 2.0)));
+        assert!(!segments_cross(pos(0.0, 0.0), pos(1.0, 0.0), pos(0.0, 1.0), pos(1.0, 1.0)));
+    }
+
+    #[test]
+    fn segments_dist_measures_the_gap() {
+        assert_eq!(segments_dist(pos(-1.0, 0.0), pos(1.0, 0.0), pos(0.0, -1.0), pos(0.0, 1.0)), 0.0);
+        assert_eq!(segments_dist(pos(0.0, 0.0), pos(10.0, 0.0), pos(5.0, 3.0), pos(5.0, 8.0)), 3.0);
+        assert_eq!(segments_dist(pos(0.0, 0.0), pos(0.0, 0.0), pos(3.0, 4.0), pos(3.0, 4.0)), 5.0);
+    }
+
+    #[test]
+    fn polygon_contains_inside_only() {
+        let corners = square(0.0, 0.0, 10.0);
+        assert!(polygon_contains(&corners, pos(5.0, 5.0)));
+        assert!(!polygon_contains(&corners, pos(15.0, 5.0)));
+        assert!(!polygon_contains(&corners, pos(5.0, -1.0)));
+
+        // Concave: the notch of a U isn't inside
+        let u = vec![pos(0.0, 0.0), pos(30.0, 0.0), pos(30.0, 30.0), pos(20.0, 30.0),
+                     pos(20.0, 10.0), pos(10.0, 10.0), pos(10.0, 30.0), pos(0.0, 30.0)];
+        assert!(polygon_contains(&u, pos(5.0, 20.0)));
+        assert!(!polygon_contains(&u, pos(15.0, 20.0)));
+    }
+
+    #[test]
+    fn blocks_walls_and_polygons() {
+        let map = map();
+
+        // Through the wall, or within half its thickness of it
+        assert!(map.blocks(pos(-20.0, 0.0), pos(20.0, 0.0)));
+        assert!(map.blocks(pos(-20.0, 53.0), pos(20.0, 53.0)));
+        assert!(!map.blocks(pos(-20.0, 60.0), pos(20.0, 60.0)));
+
+        // Into the square, or through it
+        assert!(map.blocks(pos(50.0, 0.0), pos(150.0, 0.0)));
+        assert!(map.blocks(pos(50.0, 0.0), pos(250.0, 0.0)));
+        assert!(!map.blocks(pos(50.0, 100.0), pos(250.0, 100.0)));
+    }
+
+    #[test]
+    fn portal_jumps() {
+        let map = map();
+
+        assert_eq!(map.portal_exit(pos(-190.0, 5.0)), Some(pos(300.0, 0.0)));
+        assert_eq!(map.portal_exit(pos(-170.0, 0.0)), None);
+        assert!(map.is_jump(pos(-190.0, 5.0), pos(300.0, 0.0)));
+        assert!(!map.is_jump(pos(-190.0, 5.0), pos(290.0, 0.0)));
+        assert!(!map.is_jump(pos(-100.0, 0.0), pos(300.0, 0.0)));
+    }
+
+    #[test]
+    fn clip_stops_at_the_first_obstacle() {
+        let map = map();
+
+        // Nothing in the way: left alone
+        let mut points = vec![pos(50.0, 100.0), pos(50.0, 80.0), pos(60.0, 80.0)];
+        map.clip(&mut points);
+        assert_eq!(points, vec![pos(50.0, 100.0), pos(50.0, 80.0), pos(60.0, 80.0)]);
+
+        // Cut before the segment going into the square
+        let mut points = vec![pos(50.0, 100.0), pos(50.0, 0.0), pos(150.0, 0.0), pos(150.0, 100.0)];
+        map.clip(&mut points);
+        assert_eq!(points, vec![pos(50.0, 100.0), pos(50.0, 0.0)]);
+
+        // Blocked from the start: only the start is kept
+        let mut points = vec![pos(-20.0, 0.0), pos(20.0, 0.0), pos(20.0, 10.0)];
+        map.clip(&mut points);
+        assert_eq!(points, vec![pos(-20.0, 0.0), pos(-20.0, 0.0)]);
+
+        // Jumping through a portal goes past the wall and the square
+        let mut points = vec![pos(-250.0, 0.0), pos(-195.0, 0.0), pos(300.0, 0.0), pos(350.0, 0.0)];
+        map.clip(&mut points);
+        assert_eq!(points.len(), 4);
+    }
+}
@@ -1,11 +1,16 @@
 
+use js_sys::{Array, Date, Math};
 use serde::{Serialize, Deserialize};
 use serde::de::{DeserializeOwned};
 use wasm_bindgen::prelude::*;
 
-use std::cell::RefCell;
+use crate::traits::{BaseGame, NetMsg};
+
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
 use std::rc::Rc;
 
 #[wasm_bindgen]
@@ -15,6 +20,46 @@ extern "C" {
 
 }
 
+/// How much the network layer logs to the console
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum NetLogLevel {
+    Error,   // only failures
+    Warning, // also misuse, like sending over closed streams
+    Info,    // also connection events
+    Verbose, // also every message sent and received
+}
+
+thread_local! {
+    // Shared by all the NetworkManagers, since it's set from the (global) game config
+    static LOG_LEVEL: Cell<NetLogLevel> = const { Cell::new(NetLogLevel::Info) };
+
+    // Whether messages are measured for the byte counters, which costs encoding them
+    // as JSON.  Only done while the stats are shown, the counters starting over each
+    // time it starts.
+    static MEASURE_SIZES: Cell<bool> = const { Cell::new(false) };
+}
+
+// Log the message made by 'msg_fn' if 'level' is currently enabled.  The message is
+// only formatted when it'll be logged.
+fn net_log(level: NetLogLevel, msg_fn: impl FnOnce() -> String) {
+    if level <= LOG_LEVEL.with(|l| l.get()) {
+        log(&msg_fn());
+    }
+}
+
+// Approximate size of an encoded message on the wire, for statistics, or 0 if we
+// don't measure them
+fn encoded_size(encoded: &JsValue) -> u64 {
+    if !MEASURE_SIZES.with(|m| m.get()) {
+        return 0;
+    }
+
+    match js_sys::JSON::stringify(encoded) {
+        Ok(s) => s.length() as u64,
+        Err(_) => 0,
+    }
+}
+
 #[wasm_bindgen(module = "https://esm.sh/peerjs@1.5.5?bundle-deps")]
 extern "C" {
     type Peer;
@@ -57,6 +102,88 @@ where MSG: fmt::Debug + Serialize
     pub msg: MSG,
 }
 
+// An entry of a packet sent between peers
+#[derive(Debug, Serialize, Deserialize)]
+enum NetFrame<MSG>
+where MSG: fmt::Debug + Serialize
+{
+    // A message for a stream
+    Data(NetData<MSG>),
+
+    // The sender closed the stream with the contained id, and won't send over it anymore
+    CloseStream(i32),
+}
+
+// Which sides closed a stream.  A stream is forgotten, and its id can be reused, once
+// both have.
+#[derive(Clone, Copy, Debug, Default)]
+struct ClosedStream {
+    local: bool,
+    remote: bool,
+}
+
+/// Priority of a stream's messages when the outbound queue is flushed.  Messages
+/// on higher priority streams are placed first in the packet.  All the messages of
+/// a stream share its priority, so they always stay in the order they were sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StreamPriority {
+    Low,
+    Normal,
+    High,
+}
+
+/// Traffic counters for one stream
+#[derive(Clone, Debug, Default)]
+pub struct StreamStats {
+    pub msgs_in: u64,
+    pub msgs_out: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub full_update_reqs: u64, // full updates requested by either side
+}
+
+impl StreamStats {
+    fn add(&mut self, other: &StreamStats) {
+        self.msgs_in += other.msgs_in;
+        self.msgs_out += other.msgs_out;
+        self.bytes_in += other.bytes_in;
+        self.bytes_out += other.bytes_out;
+        self.full_update_reqs += other.full_update_reqs;
+    }
+}
+
+/// Traffic statistics for one NetworkHandle
+#[derive(Clone, Debug, Default)]
+pub struct NetStats {
+    pub streams: HashMap<i32, StreamStats>,
+    pub queue_depth: usize, // number of messages in the last flushed packet
+    pub rtt: Option<f64>,   // last measured round-trip time, in seconds
+    pub bytes_measured: bool, // false if the streams' byte counters aren't kept
+}
+
+impl NetStats {
+    /// Return the sum of the stats of all the streams
+    pub fn total(&self) -> StreamStats {
+        let mut total = StreamStats::default();
+        for (_, stream) in self.streams.iter() {
+            total.add(stream);
+        }
+        total
+    }
+}
+
+/// Settings for simulating a bad connection.  Applied to the packets received on a
+/// handle, so a pair of peers can each degrade their own direction.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct NetSimConfig {
+    pub enabled: bool,
+    pub latency: f64,      // seconds of delay added to every packet
+    pub jitter: f64,       // up to this many seconds of random extra delay
+    pub drop_rate: f64,    // chance (0 to 1) that a packet is lost
+    pub dup_rate: f64,     // chance that a packet is received twice
+    pub reorder_rate: f64, // chance that a packet is held back behind later ones
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub enum NetUpdate
 {
@@ -121,12 +248,84 @@ impl fmt::Display for StreamHandle {
     }
 }
 
+/// StreamMsg
+/// A sub-message type, carried over the network by one of the variants of 'NetMsg'
+pub trait StreamMsg: fmt::Debug + Sized {
+    /// Wrap this message in its 'NetMsg' variant
+    fn into_net_msg(self) -> NetMsg;
+
+    /// Unwrap the specified 'msg', or return it back if it's a different variant
+    fn from_net_msg(msg: NetMsg) -> Result<Self, NetMsg>;
+}
+
+/// TypedStream
+/// A stream carrying only messages of type 'T'
+pub struct TypedStream<T: StreamMsg> {
+    stream: StreamHandle,
+    _msg: PhantomData<fn() -> T>,
+}
+
+impl<T: StreamMsg> TypedStream<T> {
+    pub fn new(stream: StreamHandle) -> Self {
+        Self { stream, _msg: PhantomData }
+    }
+
+    pub fn stream(&self) -> StreamHandle {
+        self.stream
+    }
+
+    /// Call 'cb' with each message received over this stream.  'cb' returns 'false'
+    /// for messages it didn't expect, which are reported, as are messages of another type.
+    pub fn process_msgs(&self, game: &mut dyn BaseGame, cb: &mut dyn FnMut(&T, &mut dyn BaseGame) -> bool) {
+        let msgs = game.network().get_stream_msgs(self.stream);
+        for outer in msgs.into_iter() {
+            match T::from_net_msg(outer) {
+                Ok(msg) => {
+                    if !cb(&msg, game) {
+                        log(&format!("Unexpected {} on stream {} :: {:?}", std::any::type_name::<T>(), self.stream, &msg));
+                    }
+                }
+                Err(other) => {
+                    log(&format!("Unexpected message on {} stream {} :: {:?}", std::any::type_name::<T>(), self.stream, &other));
+                }
+            }
+        }
+    }
+
+    pub fn send(&self, game: &mut dyn BaseGame, msg: T) {
+        game.network().send(&self.stream, msg.into_net_msg());
+    }
+}
+
+// Implemented by hand, since deriving would require 'T' to implement them too
+impl<T: StreamMsg> Clone for TypedStream<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: StreamMsg> Copy for TypedStream<T> {}
+
+impl<T: StreamMsg> PartialEq for TypedStream<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.stream == other.stream
+    }
+}
+
+impl<T: StreamMsg> Eq for TypedStream<T> {}
+
+impl<T: StreamMsg> Hash for TypedStream<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.stream.hash(state);
+    }
+}
+
 /// PeerInfo
 pub struct PeerInfo<MSG>
 where MSG: fmt::Debug + Serialize
 {
-    _is_listen: bool,
-    _is_closed: bool,
+    is_listen: bool,
+    is_closed: bool, // the connection was closed by the remote side
     _peer: Option<Peer>,
     _peer_open_closure: Option<Closure::<dyn FnMut(String)>>,
     _peer_connection_closure: Option<Closure::<dyn FnMut(JsValue)>>,
@@ -139,6 +338,24 @@ where MSG: fmt::Debug + Serialize
     received_updates: Vec<NetUpdate>,
     received_msgs: HashMap<i32, Vec<MSG>>,
     next_stream_id: i32,
+
+    // Encoded frames waiting for the next 'flush', with their stream's priority
+    outbound: Vec<(StreamPriority, JsValue)>,
+
+    // Streams closed by either side, but not yet by both
+    closed_streams: HashMap<i32, ClosedStream>,
+
+    // Ids of our streams closed by both sides, to reuse before making new ones
+    free_stream_ids: Vec<i32>,
+
+    // Priorities of streams that don't use the default
+    stream_priorities: HashMap<i32, StreamPriority>,
+
+    stats: NetStats,
+
+    // Received packets held back by the network simulator, with the time (per
+    // 'Date::now', in seconds) when they're due
+    delayed_packets: Vec<(f64, JsValue)>,
 }
 
 impl<MSG> PeerInfo<MSG>
@@ -146,8 +363,8 @@ where MSG: fmt::Debug + Serialize
 {
     fn new(is_listen: bool) -> Self {
         Self {
-            _is_listen: is_listen,
-            _is_closed: false,
+            is_listen,
+            is_closed: false,
             _peer: None,
             _peer_open_closure: None,
             _peer_connection_closure: None,
@@ -160,6 +377,175 @@ where MSG: fmt::Debug + Serialize
             received_updates: Vec::new(),
             received_msgs: HashMap::new(),
             next_stream_id: if is_listen {1} else {2},
+            outbound: Vec::new(),
+            closed_streams: HashMap::new(),
+            free_stream_ids: Vec::new(),
+            stream_priorities: HashMap::from([(0, StreamPriority::High)]),
+            stats: NetStats::default(),
+            delayed_packets: Vec::new(),
+        }
+    }
+
+    fn stream_priority(&self, stream_id: i32) -> StreamPriority {
+        *self.stream_priorities.get(&stream_id).unwrap_or(&StreamPriority::Normal)
+    }
+
+    // Return 'true' if we made the stream with the specified 'stream_id'.  The listening
+    // side makes odd ids and the connecting side even ones, so they never collide.
+    fn is_own_stream(&self, stream_id: i32) -> bool {
+        stream_id > 0 && (stream_id % 2 == 1) == self.is_listen
+    }
+
+    // Return 'true' if we closed the stream with the specified 'stream_id'
+    fn is_closed_locally(&self, stream_id: i32) -> bool {
+        self.closed_streams.get(&stream_id).is_some_and(|c| c.local)
+    }
+
+    // Forget everything about the specified 'stream_id' once both sides closed it
+    fn release_if_closed(&mut self, stream_id: i32) {
+        if let Some(closed) = self.closed_streams.get(&stream_id) {
+            if closed.local && closed.remote {
+                self.closed_streams.remove(&stream_id);
+                self.stream_priorities.remove(&stream_id);
+                self.stats.streams.remove(&stream_id);
+                if self.is_own_stream(stream_id) {
+                    self.free_stream_ids.push(stream_id);
+                }
+            }
+        }
+    }
+
+    /// Close our side of the specified 'stream_id', dropping what it received but
+    /// wasn't read, and tell the remote side
+    fn close_stream(&mut self, handle: i32, stream_id: i32) {
+        let closed = self.closed_streams.entry(stream_id).or_default();
+        if closed.local {
+            net_log(NetLogLevel::Warning, || format!("Net(close_stream) stream {}:{} already closed", &handle, &stream_id));
+            return;
+        }
+        closed.local = true;
+
+        self.received_msgs.remove(&stream_id);
+
+        // Queued with the stream's priority, so it goes after anything already sent over it
+        let frame = NetFrame::<MSG>::CloseStream(stream_id);
+        let priority = self.stream_priority(stream_id);
+        self.outbound.push((priority, serde_wasm_bindgen::to_value(&frame).unwrap()));
+
+        self.release_if_closed(stream_id);
+    }
+
+    /// Send all the queued messages as a single packet
+    fn flush(&mut self, handle: i32) {
+        if self.outbound.is_empty() {
+            return;
+        }
+
+        if self.is_closed {
+            // Nobody to send to anymore
+            self.outbound.clear();
+            return;
+        }
+
+        // Stable sort, so each stream's messages keep their order
+        let mut queued = std::mem::take(&mut self.outbound);
+        queued.sort_by_key(|(priority, _)| std::cmp::Reverse(*priority));
+        self.stats.queue_depth = queued.len();
+
+        match &self._dc {
+            Some(dc) => {
+                let packet: Array = queued.into_iter().map(|(_, encoded)| encoded).collect();
+                dc.send(&packet);
+            }
+            None => {
+                net_log(NetLogLevel::Error, || format!("Net(flush) handle:{} No DC, dropping {} msgs", handle, queued.len()));
+            }
+        }
+    }
+}
+
+impl<MSG> PeerInfo<MSG>
+where MSG: DeserializeOwned + fmt::Debug + Serialize
+{
+    /// Store the messages in the specified received 'data' packet
+    fn receive_packet(&mut self, handle: i32, data: JsValue) {
+        // Packets are arrays of frames.  A lone message is what builds from before
+        // batching send, so accept it too, to be able to reject them properly.
+        if !Array::is_array(&data) {
+            match serde_wasm_bindgen::from_value::<NetData<MSG>>(data) {
+                Ok(msg) => self.receive_msg(handle, msg, 0),
+                Err(e) => {
+                    net_log(NetLogLevel::Error, || format!("Failed parsing msg '{}' for handle: {}", e, &handle));
+                }
+            }
+            return;
+        }
+
+        for entry in Array::from(&data).iter() {
+            let size = encoded_size(&entry);
+            match serde_wasm_bindgen::from_value::<NetFrame<MSG>>(entry) {
+                Ok(NetFrame::Data(msg)) => self.receive_msg(handle, msg, size),
+                Ok(NetFrame::CloseStream(stream_id)) => {
+                    net_log(NetLogLevel::Verbose, || format!("Net(close_stream) remote closed {}:{}", &handle, &stream_id));
+                    self.received_msgs.remove(&stream_id);
+                    self.closed_streams.entry(stream_id).or_default().remote = true;
+                    self.release_if_closed(stream_id);
+                }
+                Err(e) => {
+                    net_log(NetLogLevel::Error, || format!("Failed parsing msg '{}' for handle: {}", e, &handle));
+                }
+            }
+        }
+    }
+
+    fn receive_msg(&mut self, handle: i32, msg: NetData<MSG>, size: u64) {
+        if self.is_closed_locally(msg.stream_id) {
+            // Sent before the remote side knew we closed the stream
+            net_log(NetLogLevel::Verbose, || format!("Net(data) handle: {}, dropped for closed stream :{:?}", &handle, &msg));
+            return;
+        }
+
+        let stream_stats = self.stats.streams.entry(msg.stream_id).or_default();
+        stream_stats.msgs_in += 1;
+        stream_stats.bytes_in += size;
+
+        net_log(NetLogLevel::Verbose, || format!("Net(data) handle: {}, data:{:?}", &handle, &msg));
+        self.received_msgs.entry(msg.stream_id).or_default().push(msg.msg);
+    }
+
+    /// Hold on to the specified received 'data' packet until it's due according to
+    /// 'config', or lose it
+    fn simulate_packet(&mut self, handle: i32, data: JsValue, config: &NetSimConfig) {
+        if Math::random() < config.drop_rate {
+            net_log(NetLogLevel::Verbose, || format!("NetSim handle: {} dropped packet", &handle));
+            return;
+        }
+
+        let copies = if Math::random() < config.dup_rate {2} else {1};
+        for _ in 0..copies {
+            let mut delay = config.latency + Math::random() * config.jitter;
+            if Math::random() < config.reorder_rate {
+                // Hold it long enough for packets sent after it to overtake it
+                delay += config.latency + config.jitter + 0.1;
+            }
+            self.delayed_packets.push((Date::now() / 1000.0 + delay, data.clone()));
+        }
+    }
+
+    /// Receive the delayed packets that are due
+    fn deliver_delayed(&mut self, handle: i32) {
+        if self.delayed_packets.is_empty() {
+            return;
+        }
+
+        let now = Date::now() / 1000.0;
+        let (mut due, waiting): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.delayed_packets).into_iter().partition(|(time, _)| *time <= now);
+        self.delayed_packets = waiting;
+
+        due.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (_, data) in due.into_iter() {
+            self.receive_packet(handle, data);
         }
     }
 }
@@ -169,6 +555,8 @@ where MSG: fmt::Debug + Serialize
 {
     handle_map: HashMap<i32, PeerInfo<MSG>>,
     next_handle: i32,
+    sim_config: NetSimConfig, // for the handles without their own
+    handle_sim_configs: HashMap<i32, NetSimConfig>,
 }
 
 impl <MSG> NetworkManagerImp<MSG>
@@ -178,14 +566,14 @@ where MSG: DeserializeOwned + fmt::Debug + Serialize + 'static
     -> Closure::<dyn FnMut()>
     {
         let closure = Closure::<dyn FnMut()>::new(move || {
-            log(&format!("DC::on(open) src_id: {}, id: {}", &src_handle, &dc_handle));
+            net_log(NetLogLevel::Info, || format!("DC::on(open) src_id: {}, id: {}", &src_handle, &dc_handle));
             let mut imp = imp_rc.borrow_mut();
             match imp.handle_map.get_mut(&src_handle) {
                 Some(peer) => {
                     peer.received_updates.push(NetUpdate::NewPeer(dc_handle));
                 }
                 None => {
-                    log(&format!("Got 'open' for unknown handle: {}", &src_handle));
+                    net_log(NetLogLevel::Error, || format!("Got 'open' for unknown handle: {}", &src_handle));
                 }
             }
         });
@@ -196,22 +584,20 @@ where MSG: DeserializeOwned + fmt::Debug + Serialize + 'static
     fn register_data_closure(imp_rc: Rc<RefCell<Self>>, dc: &DataConnection, dc_handle: i32)
     -> Closure::<dyn FnMut(JsValue)>
     {
-        let closure = Closure::<dyn FnMut(JsValue)>::new(move |data| {
-            match serde_wasm_bindgen::from_value::<NetData<MSG>>(data) {
-                Ok(msg) => {
-                    log(&format!("Net(data) handle: {}, data:{:?}", &dc_handle, &msg));
-                    let mut imp = imp_rc.borrow_mut();
-                    match imp.handle_map.get_mut(&dc_handle) {
-                        Some(closure_peer) => {
-                            closure_peer.received_msgs.entry(msg.stream_id).or_default().push(msg.msg);
-                        }
-                        None => {
-                            log(&format!("Got update for unknown handle: {}", &dc_handle));
-                        }
+        let closure = Closure::<dyn FnMut(JsValue)>::new(move |data: JsValue| {
+            let imp = &mut *imp_rc.borrow_mut();
+            let sim_config = imp.handle_sim_configs.get(&dc_handle).unwrap_or(&imp.sim_config);
+            match imp.handle_map.get_mut(&dc_handle) {
+                Some(closure_peer) => {
+                    if sim_config.enabled {
+                        closure_peer.simulate_packet(dc_handle, data, sim_config);
+                    }
+                    else {
+                        closure_peer.receive_packet(dc_handle, data);
                     }
                 }
-                Err(e) => {
-                    log(&format!("Failed parsing msg '{}' for handle: {}", e, &dc_handle));
+                None => {
+                    net_log(NetLogLevel::Error, || format!("Got update for unknown handle: {}", &dc_handle));
                 }
             }
         });
@@ -219,12 +605,20 @@ where MSG: DeserializeOwned + fmt::Debug + Serialize + 'static
         closure
     }
 
-    fn register_close_closure(_imp_rc: Rc<RefCell<Self>>, dc: &DataConnection, dc_handle: i32)
+    fn register_close_closure(imp_rc: Rc<RefCell<Self>>, dc: &DataConnection, dc_handle: i32)
     -> Closure::<dyn FnMut()>
     {
         let closure = Closure::<dyn FnMut()>::new(move || {
-            log(&format!("DC::on(close) id: {}", &dc_handle));
-            // TODO handle
+            net_log(NetLogLevel::Info, || format!("DC::on(close) id: {}", &dc_handle));
+
+            // Closing the connection ourselves can fire this while we're borrowed.  The
+            // handle is gone by then anyway.
+            if let Ok(mut imp) = imp_rc.try_borrow_mut() {
+                if let Some(peer) = imp.handle_map.get_mut(&dc_handle) {
+                    peer.is_closed = true;
+                    peer.received_updates.push(NetUpdate::Closed);
+                }
+            }
         });
         dc.on_cb("close", closure.as_ref().unchecked_ref());
         closure
@@ -234,7 +628,7 @@ where MSG: DeserializeOwned + fmt::Debug + Serialize + 'static
     -> Closure::<dyn FnMut(JsValue)>
     {
         let closure = Closure::<dyn FnMut(JsValue)>::new(move |err | {
-            log(&format!("DC::on(error) id: {}, error: {:?}", &dc_handle, &err));
+            net_log(NetLogLevel::Error, || format!("DC::on(error) id: {}, error: {:?}", &dc_handle, &err));
             // TODO handle
         });
         dc.on_cb("error", closure.as_ref().unchecked_ref());
@@ -265,6 +659,8 @@ where MSG: Serialize + DeserializeOwned + fmt::Debug + 'static {
             imp: Rc::new(RefCell::new(NetworkManagerImp::<MSG> {
                 handle_map: HashMap::new(),
                 next_handle: 1,
+                sim_config: NetSimConfig::default(),
+                handle_sim_configs: HashMap::new(),
             })),
         }
     }
@@ -280,7 +676,7 @@ where MSG: Serialize + DeserializeOwned + fmt::Debug + 'static {
         let imp_ref = self.imp.clone();
         let local_address: String = address.into();
         let open_closure = Closure::<dyn FnMut(String)>::new(move |id: String| {
-            log(&format!("Net(Peer::open) handle: {}, address: {}, id: {}", &handle, &local_address, &id));
+            net_log(NetLogLevel::Info, || format!("Net(Peer::open) handle: {}, address: {}, id: {}", &handle, &local_address, &id));
 
             let open_imp = &mut *imp_ref.borrow_mut();
             match open_imp.handle_map.get_mut(&handle) {
@@ -289,15 +685,22 @@ where MSG: Serialize + DeserializeOwned + fmt::Debug + 'static {
                     NetworkManagerImp::<MSG>::handle_new_connection(imp_ref.clone(), info, dc, handle, handle);
                 },
                 None => {
-                    log(&format!("Net(Peer::open) handle:{} already closed", &handle));
+                    net_log(NetLogLevel::Info, || format!("Net(Peer::open) handle:{} already closed", &handle));
                 }
             }
         });
         new_peer.on_cb("open", open_closure.as_ref().unchecked_ref());
 
+        let error_imp = self.imp.clone();
         let error_closure = Closure::<dyn FnMut(JsValue)>::new(move |err| {
-            log(&format!("Net(Peer::error) handle: {}, err: {:?}", &handle, &err));
-            // TODO handle
+            net_log(NetLogLevel::Error, || format!("Net(Peer::error) handle: {}, err: {:?}", &handle, &err));
+
+            // e.g. nobody is listening at the address
+            if let Ok(mut imp) = error_imp.try_borrow_mut() {
+                if let Some(info) = imp.handle_map.get_mut(&handle) {
+                    info.received_updates.push(NetUpdate::ConnectFail);
+                }
+            }
         });
         new_peer.on_cb("error", error_closure.as_ref().unchecked_ref());
 
@@ -320,7 +723,7 @@ where MSG: Serialize + DeserializeOwned + fmt::Debug + 'static {
         let new_peer = Peer::new_with_str(address);
 
         let open_closure = Closure::<dyn FnMut(String)>::new(move |id: String| {
-            log(&format!("Net(Peer::open) handle: {}, id: {}", &handle, &id));
+            net_log(NetLogLevel::Info, || format!("Net(Peer::open) handle: {}, id: {}", &handle, &id));
         });
         new_peer.on_cb("open", open_closure.as_ref().unchecked_ref());
 
@@ -330,7 +733,7 @@ where MSG: Serialize + DeserializeOwned + fmt::Debug + 'static {
             let dc_handle = inner_imp.next_handle;
             inner_imp.next_handle += 1;
 
-            log(&format!("Net(Peer::connection) handle: {}, new_handle: {}", &handle, &dc_handle));
+            net_log(NetLogLevel::Info, || format!("Net(Peer::connection) handle: {}, new_handle: {}", &handle, &dc_handle));
 
             let mut conn_peer = PeerInfo::<MSG>::new(true);
 
@@ -339,9 +742,16 @@ where MSG: Serialize + DeserializeOwned + fmt::Debug + 'static {
         });
         new_peer.on_cb("connection", connection_closure.as_ref().unchecked_ref());
 
+        let error_imp = self.imp.clone();
         let error_closure = Closure::<dyn FnMut(JsValue)>::new(move |err| {
-            log(&format!("Net(Peer::error) handle: {}, err: {:?}", &handle, &err));
-            // TODO handle
+            net_log(NetLogLevel::Error, || format!("Net(Peer::error) handle: {}, err: {:?}", &handle, &err));
+
+            // e.g. the address is already taken
+            if let Ok(mut imp) = error_imp.try_borrow_mut() {
+                if let Some(info) = imp.handle_map.get_mut(&handle) {
+                    info.received_updates.push(NetUpdate::ListenFail);
+                }
+            }
         });
         new_peer.on_cb("error", error_closure.as_ref().unchecked_ref());
 
@@ -357,63 +767,182 @@ where MSG: Serialize + DeserializeOwned + fmt::Debug + 'static {
     }
 
     /// Cancel the operation/close the connection associated with the specified 'handle'
-    pub fn _close(&mut self, NetworkHandle(handle): NetworkHandle) {
+    pub fn close(&mut self, NetworkHandle(handle): NetworkHandle) {
         let imp = &mut *self.imp.borrow_mut();
 
         match imp.handle_map.remove(&handle) {
-            Some(info) => {
+            Some(mut info) => {
+                // Don't lose anything sent this frame, like a goodbye
+                info.flush(handle);
+
                 if let Some(dc) = info._dc {
                     dc.close();
                 }
                 if let Some(peer) = info._peer {
                     peer.destroy()
                 }
-                log(&format!("Net(close) handle:{}", &handle));
+                net_log(NetLogLevel::Info, || format!("Net(close) handle:{}", &handle));
             }
             None => {
-                log(&format!("Net(close) handle:{} already closed", &handle));
+                net_log(NetLogLevel::Info, || format!("Net(close) handle:{} already closed", &handle));
             }
         }
     }
 
-    /// Send the specified 'msg' over the specified 'stream'
+    /// Queue the specified 'msg' to be sent over the specified 'stream' on the next 'flush'
     pub fn send(&mut self, stream: &StreamHandle, msg: MSG) {
         let imp = &mut *self.imp.borrow_mut();
 
-        let send_msg = NetData::<MSG> {
+        let send_msg = NetFrame::<MSG>::Data(NetData::<MSG> {
             stream_id: stream.stream_id,
             msg
-        };
-
-        let encoded = serde_wasm_bindgen::to_value(&send_msg).unwrap();
+        });
 
-        match imp.handle_map.get(&stream.handle.0) {
+        match imp.handle_map.get_mut(&stream.handle.0) {
             Some(info) => {
-                match &info._dc {
-                    Some(dc) => {
-                        log(&format!("Net(send) handle:{}, msg: {:?}", stream.handle, &send_msg));
-                        dc.send(&encoded);
-                    },
-                    None => {
-                        log(&format!("Net(send) handle:{} No DC", stream.handle));
-                    }
+                if info.is_closed_locally(stream.stream_id) {
+                    net_log(NetLogLevel::Warning, || format!("Net(send) stream {} is closed, dropping msg: {:?}", stream, &send_msg));
+                    return;
                 }
+
+                let encoded = serde_wasm_bindgen::to_value(&send_msg).unwrap();
+
+                net_log(NetLogLevel::Verbose, || format!("Net(send) handle:{}, msg: {:?}", stream.handle, &send_msg));
+                let stream_stats = info.stats.streams.entry(stream.stream_id).or_default();
+                stream_stats.msgs_out += 1;
+                stream_stats.bytes_out += encoded_size(&encoded);
+
+                let priority = info.stream_priority(stream.stream_id);
+                info.outbound.push((priority, encoded));
             },
             None => {
-                log(&format!("Net(send) handle:{} not found", stream.handle));
+                net_log(NetLogLevel::Error, || format!("Net(send) handle:{} not found", stream.handle));
+            }
+        }
+    }
+
+    /// Receive the packets the network simulator held back, which are now due.  Called
+    /// once per frame, before anything reads messages.
+    pub fn think(&mut self) {
+        let imp = &mut *self.imp.borrow_mut();
+
+        for (handle, info) in imp.handle_map.iter_mut() {
+            info.deliver_delayed(*handle);
+        }
+    }
+
+    /// Set how the network simulator degrades received packets: as 'config' says, or
+    /// as 'handle_configs' says for the handles it lists
+    pub fn set_sim_config(&mut self, config: &NetSimConfig, handle_configs: &[(i32, NetSimConfig)]) {
+        let imp = &mut *self.imp.borrow_mut();
+        imp.sim_config = config.clone();
+        imp.handle_sim_configs = handle_configs.iter().cloned().collect();
+    }
+
+    /// Send everything queued by 'send', as one packet per handle.  Called once per frame.
+    pub fn flush(&mut self) {
+        let imp = &mut *self.imp.borrow_mut();
+
+        for (handle, info) in imp.handle_map.iter_mut() {
+            info.flush(*handle);
+        }
+    }
+
+    /// Set the priority of messages sent over the specified 'stream'.  Streams have
+    /// 'Normal' priority unless set, except stream 0, which is 'High'.
+    pub fn set_stream_priority(&mut self, stream: &StreamHandle, priority: StreamPriority) {
+        let imp = &mut *self.imp.borrow_mut();
+
+        if let Some(info) = imp.handle_map.get_mut(&stream.handle.0) {
+            info.stream_priorities.insert(stream.stream_id, priority);
+        }
+    }
+
+    /// Set how much all the NetworkManagers log
+    pub fn set_log_level(&mut self, level: NetLogLevel) {
+        LOG_LEVEL.with(|l| l.set(level));
+    }
+
+    /// Set whether all the NetworkManagers count the bytes they send and receive.  Our
+    /// byte counters start over when counting starts, since they missed what went before.
+    pub fn set_measure_sizes(&mut self, measure: bool) {
+        let was_measuring = MEASURE_SIZES.with(|m| m.replace(measure));
+        if !measure || was_measuring {
+            return;
+        }
+
+        let imp = &mut *self.imp.borrow_mut();
+        for info in imp.handle_map.values_mut() {
+            for stream in info.stats.streams.values_mut() {
+                stream.bytes_in = 0;
+                stream.bytes_out = 0;
             }
         }
     }
 
+    /// Record the specified 'rtt' (in seconds) measured for the specified 'handle'
+    pub fn record_rtt(&mut self, NetworkHandle(handle): NetworkHandle, rtt: f64) {
+        let imp = &mut *self.imp.borrow_mut();
+
+        if let Some(info) = imp.handle_map.get_mut(&handle) {
+            info.stats.rtt = Some(rtt);
+        }
+    }
+
+    /// Count a full update request, sent or received over the specified 'stream'
+    pub fn count_full_update_req(&mut self, stream: &StreamHandle) {
+        let imp = &mut *self.imp.borrow_mut();
+
+        if let Some(info) = imp.handle_map.get_mut(&stream.handle.0) {
+            info.stats.streams.entry(stream.stream_id).or_default().full_update_reqs += 1;
+        }
+    }
+
+    /// Return the statistics of all the open handles, ordered by handle
+    pub fn stats(&self) -> Vec<(NetworkHandle, NetStats)> {
+        let imp = &*self.imp.borrow();
+
+        let mut ret: Vec<(NetworkHandle, NetStats)> = imp.handle_map.iter()
+            .map(|(handle, info)| (NetworkHandle(*handle), NetStats {
+                bytes_measured: MEASURE_SIZES.with(|m| m.get()),
+                ..info.stats.clone()
+            }))
+            .collect();
+        ret.sort_by_key(|(handle, _)| handle.0);
+        ret
+    }
+
     /// Return a new stream_id for the specified 'peer'
     pub fn new_stream(&mut self, NetworkHandle(handle): NetworkHandle) -> Option<StreamHandle> {
         let imp = &mut *self.imp.borrow_mut();
 
         let info = imp.handle_map.get_mut(&handle)?;
-        let new_id = info.next_stream_id;
-        info.next_stream_id += 2;
+        let new_id = match info.free_stream_ids.pop() {
+            Some(id) => id,
+            None => {
+                info.next_stream_id += 2;
+                info.next_stream_id - 2
+            }
+        };
         return Some(StreamHandle::new(NetworkHandle{0:handle}, new_id));
     }
+
+    /// Close the specified 'stream'.  Messages it received that weren't read yet are
+    /// dropped, and the remote side is told, so it can drop its own.  Once both sides
+    /// closed it, the stream's id may be reused by 'new_stream', so 'stream' must not
+    /// be used (or closed) again.
+    pub fn close_stream(&mut self, stream: &StreamHandle) {
+        if stream.stream_id == 0 {
+            net_log(NetLogLevel::Warning, || format!("Net(close_stream) can't close default stream {}", stream));
+            return;
+        }
+
+        let imp = &mut *self.imp.borrow_mut();
+
+        if let Some(info) = imp.handle_map.get_mut(&stream.handle.0) {
+            info.close_stream(stream.handle.0, stream.stream_id);
+        }
+    }
     pub fn new_sibling_stream(&mut self, stream: &StreamHandle) -> Option<StreamHandle> {
         self.new_stream(stream.handle)
     }
@@ -31,6 +31,13 @@ pub struct TextConfig {
     pub alpha: f64,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ShapeConfig {
+    pub stroke: bool, // outline the shape instead of filling it
+    pub style: String,
+    pub alpha: f64,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ProgressBarConfig {
     pub bg: BackgroundConfig,
@@ -122,7 +129,50 @@ impl Painter {
         c.set_global_alpha(1.0);
     }
 
-    /*
+    pub fn draw_line(&self, from: &Pos2d, to: &Pos2d, width: f64, cfg: &ShapeConfig) {
+        let c = &self.canvas;
+
+        c.set_global_alpha(cfg.alpha);
+        c.set_stroke_style_str(&cfg.style);
+        c.set_line_width(width);
+        c.set_line_cap("round");
+        c.begin_path();
+        c.move_to(from.x, from.y);
+        c.line_to(to.x, to.y);
+        c.stroke();
+
+        c.set_line_cap("butt");
+        c.set_global_alpha(1.0);
+    }
+
+    pub fn draw_polygon(&self, corners: &[Pos2d], cfg: &ShapeConfig) {
+        if corners.is_empty() {
+            return;
+        }
+
+        let c = &self.canvas;
+
+        c.set_global_alpha(cfg.alpha);
+        c.begin_path();
+        c.move_to(corners[0].x, corners[0].y);
+        for pos in corners[1..].iter() {
+            c.line_to(pos.x, pos.y);
+        }
+        c.close_path();
+
+        if cfg.stroke {
+            c.set_stroke_style_str(&cfg.style);
+            c.set_line_width(10.0);
+            c.stroke();
+        }
+        else {
+            c.set_fill_style_str(&cfg.style);
+            c.fill();
+        }
+
+        c.set_global_alpha(1.0);
+    }
+
     pub fn draw_progress_bar(&self, pos: &Pos2d, pct: f64, cfg: &ProgressBarConfig) {
         self.draw_area_background(pos, &cfg.bg);
 
@@ -141,7 +191,7 @@ impl Painter {
         self.canvas.set_global_alpha(1.0);
     }
 
-
+    /*
     pub fn draw_ring(&self, pos: &Pos2d, r1: f64, r2: f64, rad1: f64, rad2: f64, cfg: &RingConfig)
     {
         let c = &self.canvas;
@@ -0,0 +1,93 @@
+// Choosing where to place a snake, while the match is in its placing phase: every
+// start point is drawn as a marker, which players click to claim it.
+
+use crate::mouse::MouseManager;
+use crate::painter::{BackgroundConfig, TextConfig};
+use crate::traits::BaseGame;
+
+use engine_p::interpolable::Pos2d;
+use serde::{Serialize, Deserialize};
+
+/// Config types
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PlacementUiConfig {
+    // Each marker's 'offset' is relative to its start point
+    pub open_marker: BackgroundConfig,
+    pub mine_marker: BackgroundConfig,
+    pub claimed_marker: BackgroundConfig, // claimed by another player
+    pub contested_marker: BackgroundConfig, // claimed by several players
+    pub taken_marker: BackgroundConfig, // a snake is already there
+    pub countdown_text: TextConfig,
+}
+
+// How a start point looks to the player choosing
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StartPointMarker {
+    Open,
+    Mine,
+    Claimed,
+    Contested,
+    Taken,
+}
+
+/// Return the marker for every start point: the 'open' ones, with their 'claims'
+/// (one entry per claiming player) and our own claim 'mine', and the 'taken' ones
+pub fn start_point_markers(open: &[Pos2d], taken: &[Pos2d], claims: &[Pos2d],
+                           mine: Option<Pos2d>) -> Vec<(Pos2d, StartPointMarker)> {
+    let open_markers = open.iter().map(|pos| {
+        let claim_count = claims.iter().filter(|claim| *claim == pos).count();
+        let marker = if claim_count >= 2 {
+            StartPointMarker::Contested
+        }
+        else if mine == Some(*pos) {
+            StartPointMarker::Mine
+        }
+        else if claim_count == 1 {
+            StartPointMarker::Claimed
+        }
+        else {
+            StartPointMarker::Open
+        };
+        (*pos, marker)
+    });
+
+    open_markers
+        .chain(taken.iter().map(|pos| (*pos, StartPointMarker::Taken)))
+        .collect()
+}
+
+fn marker_config(marker: StartPointMarker, ui_cfg: &PlacementUiConfig) -> &BackgroundConfig {
+    match marker {
+        StartPointMarker::Open => &ui_cfg.open_marker,
+        StartPointMarker::Mine => &ui_cfg.mine_marker,
+        StartPointMarker::Claimed => &ui_cfg.claimed_marker,
+        StartPointMarker::Contested => &ui_cfg.contested_marker,
+        StartPointMarker::Taken => &ui_cfg.taken_marker,
+    }
+}
+
+/// Return the start point of the marker in 'markers' clicked in the last frame, if
+/// it can still be claimed
+pub fn clicked_start_point(mouse: &MouseManager, markers: &[(Pos2d, StartPointMarker)],
+                           ui_cfg: &PlacementUiConfig) -> Option<Pos2d> {
+    markers.iter()
+        .filter(|(_, marker)| *marker != StartPointMarker::Taken)
+        .find(|(pos, marker)| {
+            let bg = marker_config(*marker, ui_cfg);
+            mouse.is_click_in_rect(&(pos.x + bg.offset.x, pos.y + bg.offset.y).into(), bg.width, bg.height)
+        })
+        .map(|(pos, _)| *pos)
+}
+
+/// Draw the 'markers', each at its start point
+pub fn draw_start_points(game: &dyn BaseGame, markers: &[(Pos2d, StartPointMarker)], ui_cfg: &PlacementUiConfig) {
+    for (pos, marker) in markers.iter() {
+        game.painter().draw_area_background(pos, marker_config(*marker, ui_cfg));
+    }
+}
+
+/// Draw the seconds left to choose, 'time_left'
+pub fn draw_countdown(game: &dyn BaseGame, time_left: f64, ui_cfg: &PlacementUiConfig) {
+    let text = format!("Pick where to start: {:.0}s", time_left.ceil());
+    game.painter().draw_text(&text, &(0,0).into(), 1600.0, &ui_cfg.countdown_text);
+}
@@ -1,7 +1,11 @@
 
-use crate::network::{NetUpdate, NetworkHandle, StreamHandle};
-use crate::snake::{Snake, SnakeConfig};
-use crate::traits::{BaseGame, NewClientMsg, NetMsg};
+use crate::maps::MapConfig;
+use crate::network::{NetUpdate, NetworkHandle, StreamHandle, StreamPriority, TypedStream};
+use crate::powerups::{PowerUpConfig, PowerUpUiConfig, PowerUps};
+use crate::placement::{start_point_markers, StartPointMarker};
+use crate::shrink::ArenaRect;
+use crate::snake::{Snake, SnakeConfig, SnakeUiConfig};
+use crate::traits::{handle_ping, AcceptedMsg, BaseGame, NewClientMsg, NewClientReplyMsg, NetMsg};
 use crate::utils::log;
 
 use engine_p::interpolable::Pos2d;
@@ -18,6 +22,10 @@ use std::collections::HashMap;
 pub struct NewPlayerMsg {
     pub name: String,
     pub player_stream: i32,
+    #[serde(default)]
+    pub join_order: u32, // the host's is the lowest, the next lowest takes over if it leaves
+    #[serde(default)]
+    pub is_bot: bool, // bots are run by the host, and can't take over from it
 }
 
 // Sent by the host to all connected clients when a client disconnects,
@@ -35,8 +43,8 @@ pub struct UpdateChoicesMsg {
     snake_points: Vec<Pos2d>, // if empty, can't spawn
 }
 
-// Sent by a client to request to have its snake placed at a specific location
-// Sent over a 'player_stream'
+// Sent by a client to request to have its snake placed at a specific location, once
+// the placing phase is over.  Sent over a 'player_stream'
 #[derive(Debug, Deserialize, Serialize)]
 pub struct RequestSnakeMsg {
     pos: Pos2d,
@@ -48,14 +56,84 @@ pub struct RequestSnakeMsg {
 #[derive(Debug, Deserialize, Serialize)]
 pub struct NewSnakeMsg {
     pos: Pos2d,
+    points: Vec<Pos2d>, // the snake's current points, if it's been around already
     snake_stream: i32,
 }
 
+// Sent by the host to all clients while snakes are being placed, when the positions
+// players asked for change.  Sent over the players_stream
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ClaimsMsg {
+    claims: Vec<Pos2d>, // one entry per player asking for the position
+}
+
+// Sent by the host to all clients when the teams change.  Sent over the players_stream
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TeamsMsg {
+    teams: Vec<(u32, u32)>, // join order and team of every player in a team
+}
+
+// Sent by the host to the client next in line to host, with the session tokens of the
+// other clients' players, so they can resume them with it if it takes over.  Sent over
+// the players_stream
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SessionTokensMsg {
+    tokens: Vec<(u32, String)>, // join order and session token
+}
+
+// Sent by the host to all clients when the client next in line to host changes, so
+// that they all agree on who takes over.  Sent over the players_stream
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SuccessorMsg {
+    join_order: Option<u32>, // None if no client can take over
+}
+
+// A player's state, as part of the snapshot the host sends to a client that joins
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PlayerSnapshot {
+    pub name: String,
+    pub join_order: u32,
+    pub snake_points: Vec<Pos2d>, // empty if the player has no snake yet
+    #[serde(default)]
+    pub team: Option<u32>,
+}
+
+// A player's state, as saved by the host
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SavedPlayer {
+    name: String,
+    join_order: u32,
+    snake_points: Vec<Pos2d>, // empty if the player has no snake
+    is_host: bool,
+    is_bot: bool,
+    #[serde(default)]
+    team: Option<u32>,
+    #[serde(default)]
+    session_token: String, // empty if it can't be resumed
+}
+
+// Everything the HostPlayerManager knows, to restore it later.  Spectators aren't
+// saved, they just join again.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SavedPlayers {
+    // The layout 'open_positions' was taken from, so it isn't picked again while
+    // the same players are in the game
+    #[serde(default)]
+    layout: Vec<Pos2d>,
+    open_positions: Vec<Pos2d>,
+    players: Vec<SavedPlayer>,
+    next_join_order: u32,
+}
+
 // Messages sent over the players_stream, processed by the PlayerManager
 #[derive(Debug, Deserialize, Serialize)]
 pub enum PlayersMsg {
     NewPlayer(NewPlayerMsg),
     PlayerLeft(PlayerLeftMsg),
+    Claims(ClaimsMsg),
+    Teams(TeamsMsg),
+    SessionTokens(SessionTokensMsg),
+    Successor(SuccessorMsg),
 }
 
 // Messages sent over a 'player_stream', processed by a 'Player'
@@ -64,58 +142,115 @@ pub enum PlayerMsg {
     UpdateChoices(UpdateChoicesMsg),
     RequestSnake(RequestSnakeMsg),
     NewSnake(NewSnakeMsg),
+
+    // Sent by a spectating client, to become a player once the round is over
+    RequestPlay,
+
+    // Sent by the host to a spectating client which became a player
+    Promoted,
+
+    // Sent by the host when the player's snake is taken away, before the next round
+    RemoveSnake,
+
+    // Sent by the host to a client which sits out the next bracket match, as a spectator
+    Benched,
 }
 
 /// Config types
+
+// Snake start points meant for games of up to 'max_players' players
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StartLayout {
+    pub name: String,
+    pub max_players: usize,
+    pub points: Vec<Pos2d>,
+}
+
+// Order in which the open start points are offered to the players.  Players
+// pick the first one they're offered.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub enum StartAssignment {
+    InOrder, // as listed in the layout
+    Random,
+    Fair, // furthest from the other snakes first
+}
+
+// Players on the same team can't snatch each other, and their scores add up
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TeamConfig {
+    pub name: String,
+    pub color: String, // stroke style of the team's snakes
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct PlayerManagerConfig {
-    pub snake_start_points: Vec<Pos2d>,
+    pub start_layouts: Vec<StartLayout>,
+    pub start_layout: String, // name of the layout to use, or empty to pick one by player count
+    pub start_assignment: StartAssignment,
+    pub teams: Vec<TeamConfig>, // empty unless playing in teams
     pub snake: SnakeConfig,
+    pub reconnect_grace: f64, // seconds a disconnected client's player is kept for it to reconnect
 }
 
-/// Helper types
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
-struct PlayersStream(StreamHandle);
-impl PlayersStream {
-    fn process_msgs(&self, game: &mut dyn BaseGame, cb: &mut dyn FnMut(&PlayersMsg, &mut dyn BaseGame) -> bool) {
-        let msgs = game.network().get_stream_msgs(self.0);
-        for outer in msgs.iter() {
-            let mut processed = false;
-            if let NetMsg::Players(msg) = outer {
-                processed = cb(msg, game);
-            }
-            if !processed {
-                log(&format!("Unexpected players message on stream {} :: {:?}", self.0, outer));
-            }
-        }
-    }
+impl PlayerManagerConfig {
+    /// Return the start points to use for 'player_count' players
+    fn start_points(&self, player_count: usize) -> Vec<Pos2d> {
+        let named = self.start_layouts.iter().find(|layout| layout.name == self.start_layout);
+
+        // Otherwise the smallest layout with room for everyone, or the biggest one
+        let layout = named
+            .or_else(|| self.start_layouts.iter()
+                .filter(|layout| layout.max_players >= player_count)
+                .min_by_key(|layout| layout.max_players))
+            .or_else(|| self.start_layouts.iter().max_by_key(|layout| layout.max_players));
 
-    fn send(&self, game: &mut dyn BaseGame, msg: PlayersMsg) {
-        game.network().send(&self.0, NetMsg::Players(msg));
+        layout.map_or(Vec::new(), |layout| layout.points.clone())
     }
-}
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
-struct PlayerStream(StreamHandle);
-impl PlayerStream {
-    fn process_msgs(&self, game: &mut dyn BaseGame, cb: &mut dyn FnMut(&PlayerMsg, &mut dyn BaseGame) -> bool) {
-        let msgs = game.network().get_stream_msgs(self.0);
-        for outer in msgs.iter() {
-            let mut processed = false;
-            if let NetMsg::Player(msg) = outer {
-                processed = cb(msg, game);
-            }
-            if !processed {
-                log(&format!("Unexpected player message on stream {} :: {:?}", self.0, outer));
-            }
-        }
+    /// Return the start points of 'layout' on the side of 'team'.  The layout is split
+    /// into one side per team, from left to right.
+    fn team_side(&self, layout: &[Pos2d], team: u32) -> Vec<Pos2d> {
+        let mut points = layout.to_vec();
+        points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+
+        let team_count = self.teams.len().max(1);
+        let per_team = points.len().div_ceil(team_count);
+        points.chunks(per_team.max(1)).nth(team as usize).map_or(Vec::new(), |side| side.to_vec())
     }
 
-    fn send(&self, game: &mut dyn BaseGame, msg: PlayerMsg) {
-        game.network().send(&self.0, NetMsg::Player(msg));
+    /// Return the stroke style of the snakes of 'team'
+    pub fn snake_style(&self, team: Option<u32>) -> &str {
+        team.and_then(|team| self.teams.get(team as usize)).map_or("black", |team| &team.color)
     }
 }
 
+/// Helper functions
+// Minimum distance between an open start point and an existing snake's
+const MIN_START_DISTANCE: f64 = 150.0;
+
+// Return a new random token identifying a client's session, so it can resume it.
+// Anyone with the token can take over the player, so it comes from the browser's
+// cryptographic random generator.
+fn new_session_token() -> String {
+    let mut bytes = [0u8; 16];
+    web_sys::window().expect("window")
+        .crypto().expect("crypto")
+        .get_random_values_with_u8_array(&mut bytes).expect("random values");
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// Return a new stream next to 'sibling' for snake updates.  Those are frequent and
+// superseded by the next one, so they go after everything else in a packet.
+fn new_snake_stream(game: &mut dyn BaseGame, sibling: &StreamHandle) -> StreamHandle {
+    let stream = game.network().new_sibling_stream(sibling).unwrap();
+    game.network().set_stream_priority(&stream, StreamPriority::Low);
+    stream
+}
+
+/// Helper types
+type PlayersStream = TypedStream<PlayersMsg>;
+type PlayerStream = TypedStream<PlayerMsg>;
+
 /// HostPlayer
 /// A player managed by the host
 
@@ -123,6 +258,14 @@ struct HostPlayer {
     name: String,
     snake: Option<Snake>,
 
+    // Order in which the players joined.  The host's player has the lowest.
+    join_order: u32,
+
+    // Given to the client when it joins, to resume this player if it reconnects.
+    // Empty for the self_player, the bots, and players we took over without one,
+    // which can't be resumed.
+    session_token: String,
+
     // The client's 'players_stream'
     players_stream: Option<PlayersStream>,
 
@@ -136,46 +279,47 @@ struct HostPlayer {
 
     // Do we need to send an UpdateChoicesMsg to this player?
     need_update_choices: bool,
+
+    // Spectators get updates about all the other players, but never a snake, and
+    // the other players don't know about them
+    is_spectator: bool,
+
+    // Set when a spectator asked to become a player
+    wants_to_play: bool,
+
+    // Position the player asked for while snakes are being placed, and when
+    claim: Option<(Pos2d, f64)>,
+
+    // Set when playing in teams.  Spectators keep theirs, for when they play again.
+    team: Option<u32>,
 }
 
 impl HostPlayer {
-    fn think(&mut self, game: &mut dyn BaseGame, open_positions: &mut Vec<Pos2d>, config: &PlayerManagerConfig) {
+    /// Handle per-frame processing.  'choices' are the open positions on our team's side,
+    /// and 'map' the obstacles in the arena.
+    fn think(&mut self, game: &mut dyn BaseGame, choices: &Vec<Pos2d>, placing: bool, config: &PlayerManagerConfig,
+             map: Option<&MapConfig>) {
         if let Some(stream) = self.player_stream {
             stream.process_msgs(game, &mut |outer, g| match outer {
+                PlayerMsg::RequestSnake(_) if self.is_spectator => {
+                    log(&format!("Spectator requested a snake.  player_stream {}", stream.stream()));
+                    true
+                }
+                PlayerMsg::RequestPlay => {
+                    self.wants_to_play = self.is_spectator;
+                    true
+                }
                 PlayerMsg::RequestSnake(msg) => {
-                    match open_positions.iter().position(|p| *p == msg.pos) {
-                        Some(idx) => {
-                            // Player requested an available position
-                            open_positions.remove(idx);
-
-                            let snake_stream = g.network().new_sibling_stream(&stream.0).unwrap();
-
-                            stream.send(g, PlayerMsg::NewSnake(NewSnakeMsg {
-                                pos: msg.pos,
-                                snake_stream: snake_stream.stream_id()
-                            }));
-
-                            // Make the snake for the player
-                            let mut snake = Snake::new_remote(&self.name, snake_stream, &msg.pos);
-
-                            // Inform all other players about our snake
-                            for (_, peer_stream) in self.peer_streams.iter() {
-                                let peer_snake_stream = g.network().new_sibling_stream(&peer_stream.0).unwrap();
-                                snake.add_peer(peer_snake_stream);
-                                peer_stream.send(g, PlayerMsg::NewSnake(NewSnakeMsg {
-                                    pos: msg.pos,
-                                    snake_stream: peer_snake_stream.stream_id(),
-                                }));
-                            }
-
-                            self.snake = Some(snake);
-                        },
-                        None => {
-                            // Player requested an invalid position.
-                            log(&format!("Player requested invalid snake position.  player_stream {}, msg: {:?}, available: {:?}",
-                                stream.0, outer, &open_positions));
-                            self.need_update_choices = true;
-                        }
+                    if placing && self.snake.is_none() && choices.contains(&msg.pos) {
+                        // Player requested an available position.  The earliest request
+                        // for it gets it, once placing is over.
+                        self.claim = Some((msg.pos, g.now()));
+                    }
+                    else {
+                        // Player requested an invalid position, or too late
+                        log(&format!("Player requested invalid snake position.  player_stream {}, msg: {:?}, available: {:?}",
+                            stream.stream(), outer, &choices));
+                        self.need_update_choices = true;
                     }
                     true
                 }
@@ -184,65 +328,156 @@ impl HostPlayer {
 
             if self.need_update_choices {
                 stream.send(game, PlayerMsg::UpdateChoices(UpdateChoicesMsg {
-                    snake_points: open_positions.clone()
+                    snake_points: choices.clone()
                 }));
                 self.need_update_choices = false;
             }
         }
 
         if let Some(snake) = &mut self.snake {
-            snake.think(game, &config.snake);
+            snake.set_style(config.snake_style(self.team));
+            snake.think(game, &config.snake, map);
         }
     }
 
-    fn draw(&self, game: &dyn BaseGame) {
+    fn draw(&self, game: &dyn BaseGame, map: Option<&MapConfig>, ui_cfg: &SnakeUiConfig) {
         if let Some(snake) = &self.snake {
-            snake.draw(game);
+            snake.draw(game, map);
+            snake.draw_meters(game, map, ui_cfg);
         }
     }
 
+    /// Give this player a snake at 'pos', and tell everyone about it
+    fn place_snake(&mut self, game: &mut dyn BaseGame, pos: Pos2d) {
+        let mut snake = match self.player_stream {
+            Some(stream) => {
+                let snake_stream = new_snake_stream(game, &stream.stream());
+
+                stream.send(game, PlayerMsg::NewSnake(NewSnakeMsg {
+                    pos,
+                    points: Vec::new(),
+                    snake_stream: snake_stream.stream_id()
+                }));
+
+                Snake::new_remote(&self.name, snake_stream, &pos)
+            }
+            None => Snake::new_local(&self.name, &pos),
+        };
+
+        // Inform all other players about our snake
+        for (_, peer_stream) in self.peer_streams.iter() {
+            let peer_snake_stream = new_snake_stream(game, &peer_stream.stream());
+            snake.add_peer(peer_snake_stream);
+            peer_stream.send(game, PlayerMsg::NewSnake(NewSnakeMsg {
+                pos,
+                points: Vec::new(),
+                snake_stream: peer_snake_stream.stream_id(),
+            }));
+        }
+
+        self.snake = Some(snake);
+        self.claim = None;
+    }
+
+    /// Take away our snake, telling everyone.  Return where it started, if we had one.
+    fn remove_snake(&mut self, game: &mut dyn BaseGame) -> Option<Pos2d> {
+        let snake = self.snake.take()?;
+        let pos = snake.get_start_pos();
+
+        if let Some(stream) = self.player_stream {
+            stream.send(game, PlayerMsg::RemoveSnake);
+        }
+        for (_, peer_stream) in self.peer_streams.iter() {
+            peer_stream.send(game, PlayerMsg::RemoveSnake);
+        }
+
+        snake.close_streams(game);
+        Some(pos)
+    }
+
     /// Ensure that we have a peer_stream for every stream in 'other_streams', except for our own players_stream
     fn ensure_peer_streams(&mut self, game: &mut dyn BaseGame, other_streams: &Vec<Option<PlayersStream>>) {
+        if self.is_spectator {
+            // Nobody needs to know about spectators
+            return;
+        }
+
         for other_stream_opt in other_streams {
             if self.players_stream == *other_stream_opt {
                 continue;
             }
 
             let name = &self.name;
+            let join_order = self.join_order;
+            let is_bot = self.snake.as_ref().is_some_and(|snake| snake.is_bot());
 
             let my_snake = &mut self.snake;
 
             if let Some(other_stream) = other_stream_opt {
                 self.peer_streams.entry(*other_stream).or_insert_with(|| {
                     // Make a new peer_stream between us and 'other_stream'
-                    let new_stream = game.network().new_sibling_stream(&other_stream.0).unwrap();
+                    let new_stream = game.network().new_sibling_stream(&other_stream.stream()).unwrap();
                     other_stream.send(game, PlayersMsg::NewPlayer(NewPlayerMsg {
                         name: name.clone(),
                         player_stream: new_stream.stream_id(),
+                        join_order,
+                        is_bot,
                     }));
 
                     // If we have a snake, also peer the snake
                     if let Some(snake) = my_snake.as_mut() {
-                        let snake_stream = game.network().new_sibling_stream(&other_stream.0).unwrap();
+                        let snake_stream = new_snake_stream(game, &other_stream.stream());
                         snake.add_peer(snake_stream);
 
-                        PlayerStream(new_stream).send(game, PlayerMsg::NewSnake(NewSnakeMsg {
+                        PlayerStream::new(new_stream).send(game, PlayerMsg::NewSnake(NewSnakeMsg {
                             pos: snake.get_start_pos(),
+                            points: snake.points().clone(),
                             snake_stream: snake_stream.stream_id(),
                         }));
                     }
 
-                    PlayerStream{0: new_stream}
+                    PlayerStream::new(new_stream)
                 });
             }
         }
     }
 
-    fn remove_peer_stream(&mut self, game: &mut dyn BaseGame, key_stream: &PlayersStream) {
-        if let Some(my_stream) = self.peer_streams.remove(key_stream) {
-            key_stream.send(game, PlayersMsg::PlayerLeft(PlayerLeftMsg {
-                player_stream: my_stream.0.stream_id()
+    /// Tell our client, which reconnected, about the snake it already has, over a new stream
+    fn resume_snake(&mut self, game: &mut dyn BaseGame) {
+        if let (Some(stream), Some(snake)) = (self.player_stream, &mut self.snake) {
+            let snake_stream = new_snake_stream(game, &stream.stream());
+            snake.set_remote_stream(snake_stream);
+
+            stream.send(game, PlayerMsg::NewSnake(NewSnakeMsg {
+                pos: snake.get_start_pos(),
+                points: snake.points().clone(),
+                snake_stream: snake_stream.stream_id(),
+            }));
+        }
+    }
+
+    /// Forget the peer_stream to the player with the specified 'key_stream', who left.
+    /// Its connection is gone, so there's nobody to tell.
+    fn remove_peer_stream(&mut self, key_stream: &PlayersStream) {
+        self.peer_streams.remove(key_stream);
+
+        if let Some(snake) = &mut self.snake {
+            snake.remove_peers_on(key_stream.stream().handle());
+        }
+    }
+
+    /// Tear down this player, who left: tell all the other players, and close the
+    /// streams used to tell them about it
+    fn teardown(self, game: &mut dyn BaseGame) {
+        for (other_stream, my_stream) in self.peer_streams.iter() {
+            other_stream.send(game, PlayersMsg::PlayerLeft(PlayerLeftMsg {
+                player_stream: my_stream.stream().stream_id()
             }));
+            game.network().close_stream(&my_stream.stream());
+        }
+
+        if let Some(snake) = self.snake {
+            snake.close_streams(game);
         }
     }
 }
@@ -250,49 +485,936 @@ impl HostPlayer {
 /// HostPlayerManager
 /// PlayerManager used when we're acting as the host
 pub struct HostPlayerManager {
-    // Available positions for snakes
+    // Start points of the current layout
+    layout: Vec<Pos2d>,
+
+    // Available positions for snakes, in the order they're offered
     open_positions: Vec<Pos2d>,
 
+    // What the clients were last told about 'open_positions', and the players' claims on them
+    sent_positions: Vec<Pos2d>,
+    sent_claims: Vec<Pos2d>,
+
+    // What the clients were last told about the teams
+    sent_teams: Vec<(u32, u32)>,
+
+    // The client next in line to host, and the session tokens it was last told about
+    sent_tokens: Option<(NetworkHandle, Vec<(u32, String)>)>,
+
+    // What the clients were last told about who's next in line to host, if anything
+    sent_successor: Option<Option<u32>>,
+
+    // Set while the players choose where to place their snake
+    placing: bool,
+
     // the key is the corresponding client's network handle, or None for the host
     players: HashMap<Option<NetworkHandle>, HostPlayer>,
+
+    // Players whose client's connection dropped, by join order, with the time
+    // at which we stop waiting for the client to reconnect
+    disconnected: HashMap<u32, (HostPlayer, f64)>,
+
+    // Players whose snake we drive ourselves
+    bots: Vec<HostPlayer>,
+
+    // join_order for the next client
+    next_join_order: u32,
 }
 
 impl HostPlayerManager {
     pub fn new(self_name: &str, config: &PlayerManagerConfig) -> Self {
-        let mut open_positions = config.snake_start_points.clone();
-        open_positions.remove(0); // Remove the position the host is using
-
         let host_player = HostPlayer {
             name: self_name.to_string(),
-            snake: Some(Snake::new_local("HostSnake", &config.snake_start_points[0])),
+            snake: None, // placed along with everyone else's
+            join_order: 0,
+            session_token: "".to_string(),
             players_stream: None,
             player_stream: None,
             peer_streams: HashMap::new(),
             need_update_choices: false,
+            is_spectator: false,
+            wants_to_play: false,
+            claim: None,
+            team: None,
         };
 
         let mut players = HashMap::new();
         players.insert(None, host_player);
 
+        let mut mgr = Self {
+            layout: Vec::new(),
+            open_positions: Vec::new(),
+            sent_positions: Vec::new(),
+            sent_claims: Vec::new(),
+            sent_teams: Vec::new(),
+            sent_tokens: None,
+            sent_successor: None,
+            placing: false,
+            players,
+            disconnected: HashMap::new(),
+            bots: Vec::new(),
+            next_join_order: 1,
+        };
+        mgr.relayout(config);
+        mgr
+    }
+
+    /// Take over as the host from the specified 'client' manager, whose host left.
+    /// Our 'join_order' player becomes the host's, and the other players' clients
+    /// are expected to reconnect to us.
+    pub fn from_client(game: &mut dyn BaseGame, client: ClientPlayerManager, join_order: u32, config: &PlayerManagerConfig) -> Self {
+        let old_host_order = client.remote_join_orders().into_iter().min();
+
+        let mut players = HashMap::new();
+        let mut disconnected = HashMap::new();
+        let mut bots = Vec::new();
+        let mut next_join_order = join_order + 1;
+        let deadline = game.now() + config.reconnect_grace;
+
+        let mut session_tokens = client.session_tokens;
+        for (_, client_player) in client.players.into_iter() {
+            next_join_order = next_join_order.max(client_player.join_order + 1);
+
+            if !client_player.is_local && Some(client_player.join_order) == old_host_order {
+                // The old host is gone for good, and its position with it
+                continue;
+            }
+
+            let mut snake = client_player.snake;
+            // Bots were driven by the old host, now by us
+            if let (Some(snake), true) = (&mut snake, client_player.is_bot) {
+                snake.make_bot();
+            }
+
+            let player = HostPlayer {
+                name: client_player.name,
+                snake,
+                join_order: client_player.join_order,
+                // Without a token from the old host, the player can't be resumed
+                session_token: session_tokens.remove(&client_player.join_order).unwrap_or_default(),
+                players_stream: None,
+                player_stream: None,
+                peer_streams: HashMap::new(),
+                need_update_choices: false,
+                is_spectator: false,
+                wants_to_play: false,
+                claim: None,
+                team: client_player.team,
+            };
+
+            if client_player.is_local {
+                players.insert(None, player);
+            }
+            else if client_player.is_bot {
+                bots.push(player);
+            }
+            else {
+                disconnected.insert(client_player.join_order, (player, deadline));
+            }
+        }
+
+        let self_player = players.entry(None).or_insert_with(|| HostPlayer {
+            name: "GameHost".to_string(),
+            snake: None,
+            join_order,
+            session_token: "".to_string(),
+            players_stream: None,
+            player_stream: None,
+            peer_streams: HashMap::new(),
+            need_update_choices: false,
+            is_spectator: false,
+            wants_to_play: false,
+            claim: None,
+            team: None,
+        });
+        self_player.join_order = join_order;
+
+        let mut mgr = Self {
+            layout: Vec::new(),
+            open_positions: Vec::new(),
+            sent_positions: Vec::new(),
+            sent_claims: Vec::new(),
+            sent_teams: Vec::new(),
+            sent_tokens: None,
+            sent_successor: None,
+            placing: false,
+            players,
+            disconnected,
+            bots,
+            next_join_order,
+        };
+        mgr.relayout(config);
+        mgr
+    }
+
+    /// Return everything we know about the players, to 'restore' later
+    pub fn save(&self) -> SavedPlayers {
+        let players = self.players.iter().map(|(key, player)| (key.is_none(), player))
+            .chain(self.disconnected.values().map(|(player, _)| (false, player)))
+            .chain(self.bots.iter().map(|player| (false, player)))
+            .filter(|(_, player)| !player.is_spectator)
+            .map(|(is_host, player)| SavedPlayer {
+                name: player.name.clone(),
+                join_order: player.join_order,
+                snake_points: player.snake.as_ref().map(|snake| snake.points().clone()).unwrap_or_default(),
+                is_host,
+                is_bot: player.snake.as_ref().is_some_and(|snake| snake.is_bot()),
+                team: player.team,
+                session_token: player.session_token.clone(),
+            })
+            .collect();
+
+        SavedPlayers {
+            layout: self.layout.clone(),
+            open_positions: self.open_positions.clone(),
+            players,
+            next_join_order: self.next_join_order,
+        }
+    }
+
+    /// Return a manager with the 'saved' players.  The clients' players wait for them
+    /// to reconnect, as after taking over from another host.
+    pub fn restore(game: &mut dyn BaseGame, saved: SavedPlayers, config: &PlayerManagerConfig) -> Self {
+        let mut players = HashMap::new();
+        let mut disconnected = HashMap::new();
+        let mut bots = Vec::new();
+        let deadline = game.now() + config.reconnect_grace;
+
+        for saved_player in saved.players.into_iter() {
+            let snake = match saved_player.snake_points.first() {
+                None => None,
+                Some(start_pos) => {
+                    let mut snake = if saved_player.is_host {
+                        Snake::new_local(&saved_player.name, start_pos)
+                    }
+                    else if saved_player.is_bot {
+                        Snake::new_bot(&saved_player.name, start_pos)
+                    }
+                    else {
+                        Snake::new_detached(&saved_player.name, start_pos)
+                    };
+
+                    if saved_player.snake_points.len() >= 2 {
+                        snake.set_points(saved_player.snake_points.clone());
+                    }
+                    Some(snake)
+                }
+            };
+
+            let player = HostPlayer {
+                name: saved_player.name,
+                snake,
+                join_order: saved_player.join_order,
+                session_token: saved_player.session_token,
+                players_stream: None,
+                player_stream: None,
+                peer_streams: HashMap::new(),
+                need_update_choices: false,
+                is_spectator: false,
+                wants_to_play: false,
+                claim: None,
+                team: saved_player.team,
+            };
+
+            if saved_player.is_host {
+                players.insert(None, player);
+            }
+            else if saved_player.is_bot {
+                bots.push(player);
+            }
+            else {
+                disconnected.insert(saved_player.join_order, (player, deadline));
+            }
+        }
+
+        if players.is_empty() {
+            log("Restored players have no host player");
+            players.insert(None, HostPlayer {
+                name: "GameHost".to_string(),
+                snake: None,
+                join_order: 0,
+                session_token: "".to_string(),
+                players_stream: None,
+                player_stream: None,
+                peer_streams: HashMap::new(),
+                need_update_choices: false,
+                is_spectator: false,
+                wants_to_play: false,
+                claim: None,
+                team: None,
+            });
+        }
+
         Self {
-            open_positions,
+            layout: saved.layout,
+            open_positions: saved.open_positions,
+            sent_positions: Vec::new(),
+            sent_claims: Vec::new(),
+            sent_teams: Vec::new(),
+            sent_tokens: None,
+            sent_successor: None,
+            placing: false,
             players,
+            disconnected,
+            bots,
+            next_join_order: saved.next_join_order,
+        }
+    }
+
+    /// Return every player, including spectators and those we're waiting on to reconnect
+    fn all_players(&self) -> impl Iterator<Item = &HostPlayer> {
+        self.players.values()
+            .chain(self.disconnected.values().map(|(player, _)| player))
+            .chain(self.bots.iter())
+    }
+
+    fn all_players_mut(&mut self) -> impl Iterator<Item = &mut HostPlayer> {
+        self.players.values_mut()
+            .chain(self.disconnected.values_mut().map(|(player, _)| player))
+            .chain(self.bots.iter_mut())
+    }
+
+    /// Return the start positions of all the snakes
+    fn taken_positions(&self) -> Vec<Pos2d> {
+        self.all_players()
+            .filter_map(|player| player.snake.as_ref().map(|snake| snake.get_start_pos()))
+            .collect()
+    }
+
+    /// Switch to the start point layout the 'config' picks for the current players,
+    /// if it changed.  Points too close to an existing snake aren't opened.  Return
+    /// true if the layout changed.
+    fn relayout(&mut self, config: &PlayerManagerConfig) -> bool {
+        // Clients that didn't finish joining yet count, so there's room for them
+        let player_count = self.all_players().filter(|player| !player.is_spectator).count();
+        let layout = config.start_points(player_count);
+        if layout == self.layout {
+            return false;
+        }
+
+        let taken = self.taken_positions();
+        self.open_positions = layout.iter()
+            .filter(|pos| taken.iter().all(|t| t.dist(**pos) >= MIN_START_DISTANCE))
+            .copied()
+            .collect();
+        self.layout = layout;
+        self.arrange_open_positions(config);
+        true
+    }
+
+    /// Order the open positions the way 'config' says to offer them
+    fn arrange_open_positions(&mut self, config: &PlayerManagerConfig) {
+        match config.start_assignment {
+            StartAssignment::InOrder => {
+                let layout = &self.layout;
+                self.open_positions.sort_by_key(|pos| layout.iter().position(|p| p == pos));
+            }
+            StartAssignment::Random => {
+                for i in (1..self.open_positions.len()).rev() {
+                    let j = (js_sys::Math::random() * (i + 1) as f64) as usize;
+                    self.open_positions.swap(i, j.min(i));
+                }
+            }
+            StartAssignment::Fair => {
+                let taken = self.taken_positions();
+                let room = |pos: &Pos2d| taken.iter().map(|t| t.dist(*pos)).fold(f64::INFINITY, f64::min);
+                self.open_positions.sort_by(|a, b| room(b).total_cmp(&room(a)));
+            }
+        }
+    }
+
+    /// Tell all the clients about the open positions on their team's side, so those
+    /// still waiting to place their snake can pick one
+    fn send_start_points(&mut self, game: &mut dyn BaseGame, config: &PlayerManagerConfig) {
+        self.sent_positions = self.open_positions.clone();
+        for (handle, player) in self.players.iter() {
+            if let (Some(handle), true) = (handle, player.players_stream.is_some()) {
+                game.network().send(&handle.default_stream(), NetMsg::StartPointsUpdate(self.choices(player.team, config)));
+            }
         }
     }
 
+    /// Return the open positions offered to the players of 'team': those on its side,
+    /// unless they're all taken
+    fn choices(&self, team: Option<u32>, config: &PlayerManagerConfig) -> Vec<Pos2d> {
+        let side = match team {
+            Some(team) => config.team_side(&self.layout, team),
+            None => return self.open_positions.clone(),
+        };
+
+        let choices: Vec<Pos2d> = self.open_positions.iter().filter(|pos| side.contains(pos)).copied().collect();
+        if choices.is_empty() {
+            self.open_positions.clone()
+        }
+        else {
+            choices
+        }
+    }
+
+    /// Put the players without a team in the smallest one, once we play in teams.
+    /// Return the team picked for the next player.
+    fn assign_teams(&mut self, config: &PlayerManagerConfig) -> Option<u32> {
+        let team_count = config.teams.len() as u32;
+        let mut sizes = vec![0; config.teams.len()];
+        for player in self.all_players_mut() {
+            player.team = player.team.filter(|team| *team < team_count);
+            if let (Some(team), false) = (player.team, player.is_spectator) {
+                sizes[team as usize] += 1;
+            }
+        }
+
+        let smallest = |sizes: &Vec<u32>| (0..team_count).min_by_key(|team| sizes[*team as usize]);
+        for player in self.all_players_mut().filter(|player| player.team.is_none() && !player.is_spectator) {
+            player.team = smallest(&sizes);
+            if let Some(team) = player.team {
+                sizes[team as usize] += 1;
+            }
+        }
+        smallest(&sizes)
+    }
+
+    /// Move the player with the specified 'join_order' to the next team
+    pub fn cycle_team(&mut self, join_order: u32, config: &PlayerManagerConfig) {
+        let team_count = config.teams.len() as u32;
+        if let Some(player) = self.all_players_mut().find(|player| player.join_order == join_order) {
+            player.team = player.team.map(|team| (team + 1) % team_count.max(1));
+        }
+    }
+
+    /// Return the join order, name and team of every player, in join order
+    pub fn roster(&self) -> Vec<(u32, String, Option<u32>)> {
+        let mut roster: Vec<(u32, String, Option<u32>)> = self.all_players()
+            .filter(|player| !player.is_spectator)
+            .map(|player| (player.join_order, player.name.clone(), player.team))
+            .collect();
+        roster.sort_by_key(|(order, _, _)| *order);
+        roster
+    }
+
+    /// Return the join order and team of every player in a team, to tell the clients
+    fn teams(&self) -> Vec<(u32, u32)> {
+        self.roster().into_iter().filter_map(|(order, _, team)| team.map(|team| (order, team))).collect()
+    }
+
     /// Add a new client with the corresponding 'players_stream'.
     pub fn add_client(&mut self, handle: NetworkHandle) {
         self.players.insert(Some(handle), HostPlayer {
             name: "".to_string(),
             snake: None,
+            join_order: 0, // assigned once its handshake is accepted
+            session_token: "".to_string(),
             players_stream: None,
             player_stream: None,
             peer_streams: HashMap::new(),
             need_update_choices: true,
+            is_spectator: false,
+            wants_to_play: false,
+            claim: None,
+            team: None,
         });
     }
 
-    pub fn think(&mut self, game: &mut dyn BaseGame, config: &PlayerManagerConfig) {
+    /// Return the handles of all the connected clients
+    pub fn client_handles(&self) -> Vec<NetworkHandle> {
+        self.players.keys().filter_map(|h| *h).collect()
+    }
+
+    /// Add a bot player, at the first open position on its team's side.  Return false
+    /// if there's no room.
+    pub fn add_bot(&mut self, game: &mut dyn BaseGame, config: &PlayerManagerConfig) -> bool {
+        let team = self.assign_teams(config);
+        let pos = match self.choices(team, config).first() {
+            Some(pos) => *pos,
+            None => return false,
+        };
+        self.open_positions.retain(|p| *p != pos);
+        let join_order = self.next_join_order;
+        self.next_join_order += 1;
+
+        let name = format!("Bot {}", join_order);
+        log(&format!("Adding {} at {:?}", &name, &pos));
+
+        let mut bot = HostPlayer {
+            snake: Some(Snake::new_bot(&name, &pos)),
+            name,
+            join_order,
+            session_token: "".to_string(),
+            players_stream: None,
+            player_stream: None,
+            peer_streams: HashMap::new(),
+            need_update_choices: false,
+            is_spectator: false,
+            wants_to_play: false,
+            claim: None,
+            team,
+        };
+
+        // Tell all the clients about it
+        let client_streams: Vec<Option<PlayersStream>> = self.players.values()
+            .filter(|p| p.players_stream.is_some())
+            .map(|p| p.players_stream)
+            .collect();
+        bot.ensure_peer_streams(game, &client_streams);
+
+        self.bots.push(bot);
+        true
+    }
+
+    /// Point the bots at the specified 'objectives' (coins, or zones), and at the other snakes
+    pub fn aim_bots(&mut self, objectives: &[Pos2d]) {
+        let heads: Vec<Pos2d> = self.players.values()
+            .chain(self.disconnected.values().map(|(player, _)| player))
+            .chain(self.bots.iter())
+            .filter_map(|player| player.snake.as_ref().map(|snake| snake.head()))
+            .collect();
+
+        for bot in self.bots.iter_mut() {
+            if let Some(snake) = &mut bot.snake {
+                let own_head = snake.head();
+                let mut goals = objectives.to_vec();
+                goals.extend(heads.iter().filter(|head| **head != own_head));
+                snake.set_bot_goals(goals);
+            }
+        }
+    }
+
+    /// Tell all the clients which one is next in line to host, if that changed, and
+    /// tell that one the session tokens of the other clients' players, should it take over
+    fn send_session_tokens(&mut self, game: &mut dyn BaseGame) {
+        let successor = self.players.iter()
+            .filter(|(handle, player)| handle.is_some() && player.players_stream.is_some() && !player.is_spectator)
+            .min_by_key(|(_, player)| player.join_order)
+            .map(|(handle, player)| (handle.unwrap(), player.join_order, player.players_stream.unwrap()));
+
+        // Players in their reconnect grace are still around for the clients, so they
+        // can't tell the successor by themselves
+        let successor_order = successor.map(|(_, order, _)| order);
+        if self.sent_successor != Some(successor_order) {
+            for player in self.players.values() {
+                if let Some(stream) = player.players_stream {
+                    stream.send(game, PlayersMsg::Successor(SuccessorMsg { join_order: successor_order }));
+                }
+            }
+            self.sent_successor = Some(successor_order);
+        }
+
+        let (handle, successor_order, stream) = match successor {
+            Some(successor) => successor,
+            None => {
+                self.sent_tokens = None;
+                return;
+            }
+        };
+
+        let mut tokens: Vec<(u32, String)> = self.all_players()
+            .filter(|player| player.join_order != successor_order && !player.session_token.is_empty())
+            .map(|player| (player.join_order, player.session_token.clone()))
+            .collect();
+        tokens.sort();
+
+        if self.sent_tokens.as_ref() != Some(&(handle, tokens.clone())) {
+            stream.send(game, PlayersMsg::SessionTokens(SessionTokensMsg { tokens: tokens.clone() }));
+            self.sent_tokens = Some((handle, tokens));
+        }
+    }
+
+    /// Handle the handshake of the client with the specified 'handle'.  Accepts it
+    /// either as a new player, or as the one it was before it disconnected.
+    fn handle_new_client(&mut self, game: &mut dyn BaseGame, handle: NetworkHandle, msg: NewClientMsg) {
+        if let Err(reason) = msg.check_compatible() {
+            log(&format!("Rejecting client {} :: {}", handle, &reason));
+            game.network().send(&handle.default_stream(),
+                                NetMsg::NewClientReply(NewClientReplyMsg::Rejected(reason)));
+
+            // The client closes the connection itself once it sees the rejection
+            self.players.remove(&Some(handle));
+            return;
+        }
+
+        let players_stream = PlayersStream::new(handle.default_stream().sibling(msg.players_stream));
+
+        // The client may be back before we noticed its old connection closing: drop
+        // that one, and resume the player from it
+        let stale = match (msg.join_order, &msg.session_token) {
+            (Some(order), Some(token)) => self.players.iter()
+                .find(|(key, player)| **key != Some(handle) && key.is_some() && player.join_order == order &&
+                                      !player.session_token.is_empty() && player.session_token == *token)
+                .and_then(|(key, _)| *key),
+            _ => None,
+        };
+        if let Some(stale) = stale {
+            log(&format!("Client {} replaces its old connection {}", handle, stale));
+            let mut player = self.detach_client(game, stale);
+            player.player_stream = None;
+            self.disconnected.insert(player.join_order, (player, game.now()));
+        }
+
+        // Players whose token we don't know can't be resumed
+        let resumable = match (msg.join_order, &msg.session_token) {
+            (Some(order), Some(token)) => match self.disconnected.get(&order) {
+                Some((player, _)) => !player.session_token.is_empty() && player.session_token == *token,
+                None => false,
+            },
+            _ => false,
+        };
+
+        let resumed = match msg.join_order {
+            Some(order) if resumable => self.disconnected.remove(&order),
+            _ => None,
+        };
+        let player = match resumed {
+            Some((mut player, _)) => {
+                log(&format!("Client {} resumed player {}", handle, &player.name));
+                player.need_update_choices = player.snake.is_none() && !player.is_spectator;
+                self.players.insert(Some(handle), player);
+                self.players.get_mut(&Some(handle)).unwrap()
+            }
+            None => {
+                match self.players.get_mut(&Some(handle)) {
+                    Some(player) => {
+                        player.session_token = new_session_token();
+                        player.join_order = self.next_join_order;
+                        self.next_join_order += 1;
+
+                        // Watch if asked to, or if there's no room left
+                        player.is_spectator = msg.spectate || self.open_positions.is_empty();
+                        player.wants_to_play = !msg.spectate; // it gets to play once there's room
+                        player.need_update_choices = !player.is_spectator;
+                        player
+                    }
+                    None => {
+                        log(&format!("Handshake from unknown client {}", handle));
+                        return;
+                    }
+                }
+            }
+        };
+
+        player.players_stream = Some(players_stream);
+
+        game.network().send(&handle.default_stream(), NetMsg::NewClientReply(NewClientReplyMsg::Accepted(AcceptedMsg {
+            session_token: player.session_token.clone(),
+            join_order: player.join_order,
+            spectator: player.is_spectator,
+        })));
+    }
+
+    /// Close the connection to the client with the specified 'handle', and return its
+    /// player, which is left without a client
+    fn detach_client(&mut self, game: &mut dyn BaseGame, handle: NetworkHandle) -> HostPlayer {
+        let mut player = self.players.remove(&Some(handle)).unwrap();
+        game.network().close(handle);
+
+        // Everything that went over the connection is gone
+        if let Some(players_stream) = player.players_stream.take() {
+            for (_, other) in self.players.iter_mut() {
+                other.remove_peer_stream(&players_stream);
+            }
+            for (_, (other, _)) in self.disconnected.iter_mut() {
+                other.remove_peer_stream(&players_stream);
+            }
+            for bot in self.bots.iter_mut() {
+                bot.remove_peer_stream(&players_stream);
+            }
+        }
+
+        player
+    }
+
+    /// Handle the connection to the client with the specified 'handle' closing.  Its
+    /// player is kept for a while, in case the client reconnects.
+    fn handle_disconnect(&mut self, game: &mut dyn BaseGame, handle: NetworkHandle, config: &PlayerManagerConfig) {
+        let mut player = self.detach_client(game, handle);
+        if player.player_stream.take().is_some() && config.reconnect_grace > 0.0 {
+            log(&format!("Waiting for player {} to reconnect", &player.name));
+            self.disconnected.insert(player.join_order, (player, game.now() + config.reconnect_grace));
+        }
+        else {
+            self.remove_player(game, player);
+        }
+    }
+
+    /// Remove the specified 'player' from the game for good
+    fn remove_player(&mut self, game: &mut dyn BaseGame, player: HostPlayer) {
+        // Its position opens up again, unless the layout changed since
+        if let Some(snake) = player.snake.as_ref().filter(|snake| self.layout.contains(&snake.get_start_pos())) {
+            self.open_positions.push(snake.get_start_pos());
+        }
+
+        player.teardown(game);
+    }
+
+    /// Let the players claim the positions they want, until 'finish_placing'
+    pub fn start_placing(&mut self) {
+        self.placing = true;
+        for (_, player) in self.players.iter_mut() {
+            player.claim = None;
+            player.need_update_choices = player.snake.is_none() && !player.is_spectator;
+        }
+    }
+
+    /// Claim the open position 'pos' for our own player, at time 'now'
+    pub fn claim_for_host(&mut self, now: f64, pos: Pos2d, config: &PlayerManagerConfig) {
+        let choices = self.choices(self.players[&None].team, config);
+        let self_player = self.players.get_mut(&None).unwrap();
+        if self.placing && self_player.snake.is_none() && !self_player.is_spectator && choices.contains(&pos) {
+            self_player.claim = Some((pos, now));
+        }
+    }
+
+    /// Place the snakes of all the players without one: at the position they claimed if
+    /// they were first to, or at the best one left on their team's side
+    pub fn finish_placing(&mut self, game: &mut dyn BaseGame, config: &PlayerManagerConfig) {
+        self.placing = false;
+
+        let mut claims: Vec<(f64, Option<NetworkHandle>, Pos2d)> = self.players.iter()
+            .filter_map(|(key, player)| player.claim.map(|(pos, time)| (time, *key, pos)))
+            .collect();
+        claims.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        for (_, key, pos) in claims.into_iter() {
+            if let Some(idx) = self.open_positions.iter().position(|p| *p == pos) {
+                self.open_positions.remove(idx);
+                self.players.get_mut(&key).unwrap().place_snake(game, pos);
+            }
+        }
+
+        self.arrange_open_positions(config);
+        for player in self.players.values_mut() {
+            player.claim = None;
+        }
+        self.place_waiting(game, config);
+
+        // Bots that sat out a bracket match don't have a snake anymore
+        for idx in 0..self.bots.len() {
+            let bot = &self.bots[idx];
+            if bot.snake.is_some() || bot.is_spectator {
+                continue;
+            }
+
+            if let Some(pos) = self.choices(bot.team, config).first().copied() {
+                self.open_positions.retain(|p| *p != pos);
+                let bot = &mut self.bots[idx];
+                bot.place_snake(game, pos);
+                bot.snake.as_mut().unwrap().make_bot();
+            }
+        }
+    }
+
+    /// Give the players without a snake one, at the first open position on their
+    /// team's side, if there's room.  Done when placing is over, and for players who
+    /// join during a round.
+    pub fn place_waiting(&mut self, game: &mut dyn BaseGame, config: &PlayerManagerConfig) {
+        let waiting: Vec<(Option<NetworkHandle>, Option<u32>)> = self.players.iter()
+            // Clients that didn't finish joining can't be told about it yet
+            .filter(|(key, player)| key.is_none() || player.player_stream.is_some())
+            .filter(|(_, player)| player.snake.is_none() && !player.is_spectator)
+            .map(|(key, player)| (*key, player.team))
+            .collect();
+
+        for (key, team) in waiting.into_iter() {
+            if let Some(pos) = self.choices(team, config).first().copied() {
+                self.open_positions.retain(|p| *p != pos);
+                self.players.get_mut(&key).unwrap().place_snake(game, pos);
+            }
+        }
+    }
+
+    /// Return how every start point on our own player's side looks to it
+    pub fn start_point_markers(&self, config: &PlayerManagerConfig) -> Vec<(Pos2d, StartPointMarker)> {
+        let self_player = &self.players[&None];
+        let mine = self_player.claim.map(|(pos, _)| pos);
+        start_point_markers(&self.choices(self_player.team, config), &self.taken_positions(), &self.claims(), mine)
+    }
+
+    /// Return the positions claimed by the players, one entry per player
+    fn claims(&self) -> Vec<Pos2d> {
+        let mut claims: Vec<Pos2d> = self.players.values()
+            .filter_map(|player| player.claim.map(|(pos, _)| pos))
+            .collect();
+        claims.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+        claims
+    }
+
+    /// Take away the snakes of all the players, so they place them again for the next
+    /// round.  Bots keep their position, and go back to it.
+    pub fn reset_snakes(&mut self, game: &mut dyn BaseGame) {
+        let mut freed = Vec::new();
+        for player in self.players.values_mut().chain(self.disconnected.values_mut().map(|(player, _)| player)) {
+            freed.extend(player.remove_snake(game));
+        }
+        let layout = &self.layout;
+        self.open_positions.extend(freed.into_iter().filter(|pos| layout.contains(pos)));
+
+        for bot in self.bots.iter_mut() {
+            if let Some(snake) = &mut bot.snake {
+                let start = snake.get_start_pos();
+                snake.set_points(vec![start, start]);
+            }
+        }
+    }
+
+    /// Destroy the segments of our own snake and the bots' that are outside 'boundary'
+    pub fn trim_snakes(&mut self, boundary: &ArenaRect) {
+        for player in self.all_players_mut() {
+            if let Some(snake) = &mut player.snake {
+                snake.trim(|pos| boundary.contains(pos));
+            }
+        }
+    }
+
+    /// Make every snake show, and our own snake and the bots' go at the speed of,
+    /// their player's 'power_ups'
+    pub fn apply_power_ups(&mut self, power_ups: &PowerUps, config: &PowerUpConfig, ui_cfg: &PowerUpUiConfig) {
+        for player in self.all_players_mut() {
+            let join_order = player.join_order;
+            if let Some(snake) = &mut player.snake {
+                power_ups.apply_to_snake(join_order, snake, config, ui_cfg);
+            }
+        }
+    }
+
+    /// Let every snake grow as long as the coins its player 'collected' allow, less the
+    /// times it was 'snatched', both by join order
+    pub fn apply_length_budgets(&mut self, collected: &HashMap<u32, u32>, snatched: &HashMap<u32, u32>) {
+        for player in self.all_players_mut() {
+            let join_order = player.join_order;
+            if let Some(snake) = &mut player.snake {
+                snake.set_coins_collected(collected.get(&join_order).copied().unwrap_or(0));
+                snake.set_times_snatched(snatched.get(&join_order).copied().unwrap_or(0));
+            }
+        }
+    }
+
+    /// Return the join order and name of everyone who'd play a series: the players,
+    /// the spectators that want to play, and the bots
+    pub fn entrants(&self) -> Vec<(u32, String)> {
+        self.players.values()
+            .filter(|player| !player.is_spectator || player.wants_to_play)
+            .chain(self.bots.iter())
+            .map(|player| (player.join_order, player.name.clone()))
+            .collect()
+    }
+
+    /// Make players of those in 'lineup' (by join order), and spectators of the other
+    /// entrants, for a bracket match
+    pub fn set_lineup(&mut self, game: &mut dyn BaseGame, lineup: &[u32]) {
+        let all_streams: Vec<Option<PlayersStream>> = self.players.values().map(|p| p.players_stream).collect();
+        let mut freed = Vec::new();
+
+        for player in self.players.values_mut().chain(self.bots.iter_mut()) {
+            let playing = lineup.contains(&player.join_order);
+            if playing && player.is_spectator {
+                player.is_spectator = false;
+                player.wants_to_play = false;
+                player.need_update_choices = player.player_stream.is_some();
+                if let Some(stream) = player.player_stream {
+                    stream.send(game, PlayerMsg::Promoted);
+                }
+
+                // The other players need to know about it, if they don't already
+                player.ensure_peer_streams(game, &all_streams);
+            }
+            else if !playing && !player.is_spectator {
+                freed.extend(player.remove_snake(game));
+                player.is_spectator = true;
+                player.wants_to_play = true;
+                if let Some(stream) = player.player_stream {
+                    stream.send(game, PlayerMsg::Benched);
+                }
+            }
+        }
+
+        let layout = &self.layout;
+        self.open_positions.extend(freed.into_iter().filter(|pos| layout.contains(pos)));
+    }
+
+    /// Make players out of the spectators that asked to, while there's room.  Only
+    /// called between rounds.
+    pub fn promote_spectators(&mut self, game: &mut dyn BaseGame) {
+        let all_streams: Vec<Option<PlayersStream>> = self.players.values().map(|p| p.players_stream).collect();
+
+        // Players that haven't picked a position yet get first dibs
+        let choosing = self.players.values().filter(|p| !p.is_spectator && p.snake.is_none()).count();
+        let mut room = self.open_positions.len().saturating_sub(choosing);
+
+        for (_, player) in self.players.iter_mut() {
+            if !player.wants_to_play || room == 0 {
+                continue;
+            }
+
+            if let Some(stream) = player.player_stream {
+                room -= 1;
+                log(&format!("Promoting spectator {} to player", &player.name));
+                player.is_spectator = false;
+                player.wants_to_play = false;
+                player.need_update_choices = true;
+                stream.send(game, PlayerMsg::Promoted);
+
+                // Now the other players need to know about it
+                player.ensure_peer_streams(game, &all_streams);
+            }
+        }
+    }
+
+    /// Return the state of every player, including those we're waiting on to reconnect
+    pub fn snapshot(&self) -> Vec<PlayerSnapshot> {
+        self.players.values()
+            .chain(self.disconnected.values().map(|(player, _)| player))
+            .chain(self.bots.iter())
+            .filter(|player| !player.is_spectator)
+            .map(|player| PlayerSnapshot {
+                name: player.name.clone(),
+                join_order: player.join_order,
+                snake_points: player.snake.as_ref().map(|snake| snake.points().clone()).unwrap_or_default(),
+                team: player.team,
+            })
+            .collect()
+    }
+
+    /// Handle per-frame processing, with the obstacles of 'map' in the arena.  Return
+    /// the handles of the clients that finished joining (or rejoining) the game.
+    pub fn think(&mut self, game: &mut dyn BaseGame, config: &PlayerManagerConfig,
+                 map: Option<&MapConfig>) -> Vec<NetworkHandle> {
+        // The layout depends on the config, which may have been updated, and on the
+        // number of players
+        if self.relayout(config) {
+            log(&format!("Switched to start points {:?}", &self.layout));
+        }
+        self.assign_teams(config);
+
+        // The open positions offered to each team
+        let choices: HashMap<Option<u32>, Vec<Pos2d>> = std::iter::once(None)
+            .chain((0..config.teams.len() as u32).map(Some))
+            .map(|team| (team, self.choices(team, config)))
+            .collect();
+
+        // Give up on players that didn't reconnect in time
+        let now = game.now();
+        let expired: Vec<u32> = self.disconnected.iter()
+            .filter(|(_, (_, deadline))| *deadline <= now)
+            .map(|(order, _)| *order)
+            .collect();
+        for order in expired.into_iter() {
+            let (player, _) = self.disconnected.remove(&order).unwrap();
+            log(&format!("Player {} did not reconnect", &player.name));
+            self.remove_player(game, player);
+        }
+
+        // Process stream-0 messages (NewClient) first, since a resumed client takes
+        // over a disconnected player before anything else happens to it
+        for handle in self.client_handles() {
+            for outer in game.network().get_stream_msgs(handle.default_stream()) {
+                match outer {
+                    NetMsg::NewClient(msg) => self.handle_new_client(game, handle, msg),
+                    NetMsg::Ping(time) => handle_ping(game, &handle.default_stream(), time),
+                    _ => {
+                        log(&format!("Unexpected message over default stream {} :: {:?}", handle.default_stream(), outer));
+                    }
+                }
+            }
+        }
+
         let mut closed_handles: Vec<NetworkHandle> = Vec::new();
 
         let mut new_players: Vec<Option<PlayersStream>> = Vec::new();
@@ -314,27 +1436,17 @@ impl HostPlayerManager {
                     }
                 }
 
-                // Process stream-0 events (NewClient)
-                for outer in game.network().get_stream_msgs(handle.default_stream()) {
-                    match outer {
-                        NetMsg::NewClient(msg) => {
-                            let players_stream = handle.default_stream().sibling(msg.players_stream);
-                            player.players_stream = Some(PlayersStream {0: players_stream});
-                        }
-                        _ => {
-                            log(&format!("Unexpected message over default stream {} :: {:?}", handle.default_stream(), outer));
-                        }
-                    }
-                }
-
                 // Process 'players_stream' messages
                 if let Some(stream) = player.players_stream {
-                    stream.process_msgs(game, &mut |outer, _g| match outer {
+                    stream.process_msgs(game, &mut |outer, g| match outer {
                         PlayersMsg::NewPlayer(msg) => {
-                            let player_stream = PlayerStream{0:stream.0.sibling(msg.player_stream)};
+                            let player_stream = PlayerStream::new(stream.stream().sibling(msg.player_stream));
                             player.name = msg.name.clone();
                             player.player_stream = Some(player_stream);
 
+                            // A resumed player already has a snake
+                            player.resume_snake(g);
+
                             new_players.push(Some(stream));
 
                             true
@@ -345,17 +1457,16 @@ impl HostPlayerManager {
             }
 
             // Allow the player itself to think
-            player.think(game, &mut self.open_positions, config);
+            player.think(game, &choices[&player.team], self.placing, config, map);
+        }
+
+        for bot in self.bots.iter_mut() {
+            bot.think(game, &choices[&bot.team], self.placing, config, map);
         }
 
         // Clean up disconnected clients
         for hndl in closed_handles.into_iter() {
-            let dead_player = self.players.remove(&Some(hndl)).unwrap();
-            if let Some(players_stream) = dead_player.players_stream {
-                for (_, player) in self.players.iter_mut() {
-                    player.remove_peer_stream(game, &players_stream);
-                }
-            }
+            self.handle_disconnect(game, hndl, config);
         }
 
         if !new_players.is_empty() {
@@ -371,7 +1482,7 @@ impl HostPlayerManager {
             for pss in new_players.iter() {
                 let key = match pss {
                     None => None,
-                    Some(pss) => Some(pss.0.handle())
+                    Some(pss) => Some(pss.stream().handle())
                 };
                 self.players.get_mut(&key).unwrap().ensure_peer_streams(game, &old_players);
             }
@@ -380,16 +1491,66 @@ impl HostPlayerManager {
             for pss in old_players.iter() {
                 let key = match pss {
                     None => None,
-                    Some(pss) => Some(pss.0.handle())
+                    Some(pss) => Some(pss.stream().handle())
                 };
                 self.players.get_mut(&key).unwrap().ensure_peer_streams(game, &new_players);
             }
+
+            // .. including the bots
+            for bot in self.bots.iter_mut() {
+                bot.ensure_peer_streams(game, &new_players);
+            }
+
+            // .. and about the teams, and who's next in line to host
+            self.sent_teams.clear();
+            self.sent_successor = None;
         }
+
+        let teams = self.teams();
+        if teams != self.sent_teams {
+            for player in self.players.values() {
+                if let Some(stream) = player.players_stream {
+                    stream.send(game, PlayersMsg::Teams(TeamsMsg { teams: teams.clone() }));
+                }
+            }
+            self.sent_teams = teams;
+
+            // Each team gets its own side of the start points
+            self.sent_positions.clear();
+        }
+
+        self.send_session_tokens(game);
+
+        if self.open_positions != self.sent_positions {
+            self.arrange_open_positions(config);
+            self.send_start_points(game, config);
+        }
+
+        let claims = self.claims();
+        if claims != self.sent_claims {
+            for player in self.players.values() {
+                if let Some(stream) = player.players_stream {
+                    stream.send(game, PlayersMsg::Claims(ClaimsMsg { claims: claims.clone() }));
+                }
+            }
+            self.sent_claims = claims;
+        }
+
+        new_players.iter().flatten().map(|pss| pss.stream().handle()).collect()
     }
 
-    pub fn draw(&self, game: &dyn BaseGame) {
+    pub fn draw(&self, game: &dyn BaseGame, map: Option<&MapConfig>, ui_cfg: &SnakeUiConfig) {
         for (_, player) in self.players.iter() {
-            player.draw(game);
+            player.draw(game, map, ui_cfg);
+        }
+
+        // Snakes of disconnected players stay in place while we wait for them
+        for (_, (player, _)) in self.disconnected.iter() {
+            player.draw(game, map, ui_cfg);
+        }
+
+        for bot in self.bots.iter() {
+            bot.draw(game, map, ui_cfg);
         }
     }
 }
@@ -400,48 +1561,92 @@ struct ClientPlayer {
     name: String,
     snake: Option<Snake>,
 
+    // Order in which the player joined, as told by the host.  Not known for our own.
+    join_order: u32,
+
+    // Only ever set for our own player
+    is_spectator: bool,
+
+    is_bot: bool,
+
+    // Only used for our own player: where we can place our snake, and where we asked to
+    choices: Vec<Pos2d>,
+    claim: Option<Pos2d>,
+
+    // As told by the host, when playing in teams
+    team: Option<u32>,
+
     // Our 'player' stream for this player with the host.
     player_stream: PlayerStream,
 }
 
 impl ClientPlayer {
-    fn think(&mut self, game: &mut dyn BaseGame, config: &PlayerManagerConfig) {
+    fn think(&mut self, game: &mut dyn BaseGame, config: &PlayerManagerConfig, map: Option<&MapConfig>) {
         let stream = self.player_stream;
         stream.process_msgs(game, &mut |outer, g| match outer {
             PlayerMsg::UpdateChoices(msg) => {
-                if self.snake.is_none() && msg.snake_points.len() > 0 {
-                    // Try to place our snake at the first available location
-                    self.player_stream.send(g, PlayerMsg::RequestSnake(RequestSnakeMsg {
-                        pos: msg.snake_points[0]
-                    }));
-                }
+                // Sent when our claim was refused, among other things
+                self.choices = msg.snake_points.clone();
+                self.claim = self.claim.filter(|pos| self.choices.contains(pos));
                 true
             }
             PlayerMsg::NewSnake(msg) => {
                 let mut snake;
-                let snake_stream = self.player_stream.0.sibling(msg.snake_stream);
+                let snake_stream = self.player_stream.stream().sibling(msg.snake_stream);
                 if self.is_local {
                     snake = Snake::new_local(&self.name, &msg.pos);
+                    g.network().set_stream_priority(&snake_stream, StreamPriority::Low);
                     snake.add_peer(snake_stream);
                 }
                 else {
                     snake = Snake::new_remote(&self.name, snake_stream, &msg.pos);
                 }
 
+                if msg.points.len() >= 2 {
+                    // A snake that was around before we (re)joined
+                    snake.set_points(msg.points.clone());
+                }
+
                 self.snake = Some(snake);
+                self.claim = None;
+                true
+            }
+            PlayerMsg::Promoted => {
+                self.is_spectator = false;
+                true
+            }
+            PlayerMsg::Benched => {
+                self.is_spectator = true;
+                true
+            }
+            PlayerMsg::RemoveSnake => {
+                if let Some(snake) = self.snake.take() {
+                    snake.close_streams(g);
+                }
                 true
             }
             _ => false
         });
 
         if let Some(snake) = &mut self.snake {
-            snake.think(game, &config.snake);
+            snake.set_style(config.snake_style(self.team));
+            snake.think(game, &config.snake, map);
         }
     }
 
-    fn draw(&self, game: &dyn BaseGame) {
+    fn draw(&self, game: &dyn BaseGame, map: Option<&MapConfig>, ui_cfg: &SnakeUiConfig) {
         if let Some(snake) = &self.snake {
-            snake.draw(game);
+            snake.draw(game, map);
+            snake.draw_meters(game, map, ui_cfg);
+        }
+    }
+
+    /// Close the streams used for this player, who left
+    fn close(self, game: &mut dyn BaseGame) {
+        game.network().close_stream(&self.player_stream.stream());
+
+        if let Some(snake) = self.snake {
+            snake.close_streams(game);
         }
     }
 }
@@ -452,64 +1657,230 @@ pub struct ClientPlayerManager {
     // the key is the player's player_stream.
     players: HashMap<StreamHandle, ClientPlayer>,
     host_players_stream: PlayersStream,
+
+    // Join order of the client next in line to host, as told by the host
+    successor: Option<u32>,
+
+    // Session tokens of the other clients' players, by join order, if we're next in
+    // line to host
+    session_tokens: HashMap<u32, String>,
+
+    // Positions claimed by the players while snakes are being placed, as told by the host
+    claims: Vec<Pos2d>,
 }
 
 impl ClientPlayerManager {
-    pub fn new(self_name: &str, host_handle: NetworkHandle, game: &mut dyn BaseGame) -> Self {
+    /// Join the host at 'host_handle', as a spectator if 'spectate' is set.  If we were
+    /// in the game before, the specified 'session_token' and 'join_order' resume our player.
+    pub fn new(self_name: &str, host_handle: NetworkHandle, spectate: bool, session_token: Option<String>,
+               join_order: Option<u32>, game: &mut dyn BaseGame) -> Self {
         let players_stream = game.network().new_stream(host_handle).unwrap();
         let player_stream = game.network().new_stream(host_handle).unwrap();
 
-        game.network().send(&host_handle.default_stream(), NetMsg::NewClient(NewClientMsg {
-            players_stream: players_stream.stream_id(),
-        }));
+        game.network().send(&host_handle.default_stream(),
+                            NetMsg::NewClient(NewClientMsg::new(players_stream.stream_id(), spectate, session_token, join_order)));
 
-        game.network().send(&players_stream, NetMsg::Players(PlayersMsg::NewPlayer(NewPlayerMsg {
+        PlayersStream::new(players_stream).send(game, PlayersMsg::NewPlayer(NewPlayerMsg {
             name: "GameClient".to_string(),
             player_stream: player_stream.stream_id(),
-        })));
+            join_order: 0, // assigned by the host
+            is_bot: false,
+        }));
 
         let self_player = ClientPlayer {
             is_local: true,
             name: self_name.to_string(),
             snake: None,
-            player_stream: PlayerStream{0:player_stream},
+            join_order: join_order.unwrap_or(0),
+            is_spectator: spectate,
+            is_bot: false,
+            choices: Vec::new(),
+            claim: None,
+            team: None,
+            player_stream: PlayerStream::new(player_stream),
         };
 
         Self {
             players: HashMap::from([
                 (player_stream, self_player)
             ]),
-            host_players_stream: PlayersStream{0:players_stream},
+            host_players_stream: PlayersStream::new(players_stream),
+            successor: None,
+            session_tokens: HashMap::new(),
+            claims: Vec::new(),
         }
     }
 
-    pub fn think(&mut self, game: &mut dyn BaseGame, config: &PlayerManagerConfig) {
+    /// Handle per-frame processing, with the obstacles of 'map' in the arena
+    pub fn think(&mut self, game: &mut dyn BaseGame, config: &PlayerManagerConfig, map: Option<&MapConfig>) {
         let stream = self.host_players_stream;
-        stream.process_msgs(game, &mut |outer, _g| match outer {
+        stream.process_msgs(game, &mut |outer, g| match outer {
             PlayersMsg::NewPlayer(msg) => {
-                let player_stream = self.host_players_stream.0.sibling(msg.player_stream);
+                let player_stream = self.host_players_stream.stream().sibling(msg.player_stream);
                 self.players.insert(player_stream, ClientPlayer {
                     is_local: false,
                     name: msg.name.clone(),
                     snake: None,
-                    player_stream: PlayerStream{0:player_stream},
+                    join_order: msg.join_order,
+                    is_spectator: false,
+                    is_bot: msg.is_bot,
+                    choices: Vec::new(),
+                    claim: None,
+                    team: None,
+                    player_stream: PlayerStream::new(player_stream),
                 });
                 true
             },
             PlayersMsg::PlayerLeft(msg) => {
-                self.players.remove(&self.host_players_stream.0.sibling(msg.player_stream));
+                if let Some(player) = self.players.remove(&self.host_players_stream.stream().sibling(msg.player_stream)) {
+                    player.close(g);
+                }
+                true
+            },
+            PlayersMsg::Claims(msg) => {
+                self.claims = msg.claims.clone();
+                true
+            },
+            PlayersMsg::Teams(msg) => {
+                for player in self.players.values_mut() {
+                    player.team = msg.teams.iter().find(|(order, _)| *order == player.join_order).map(|(_, team)| *team);
+                }
+                true
+            },
+            PlayersMsg::SessionTokens(msg) => {
+                self.session_tokens = msg.tokens.iter().cloned().collect();
+                true
+            },
+            PlayersMsg::Successor(msg) => {
+                self.successor = msg.join_order;
                 true
             },
         });
 
         for (_, player) in self.players.iter_mut() {
-            player.think(game, config);
+            player.think(game, config, map);
         }
     }
 
-    pub fn draw(&self, game: &dyn BaseGame) {
+    pub fn draw(&self, game: &dyn BaseGame, map: Option<&MapConfig>, ui_cfg: &SnakeUiConfig) {
         for (_, player) in self.players.iter() {
-            player.draw(game);
+            player.draw(game, map, ui_cfg);
+        }
+    }
+
+    /// Destroy the segments of our own snake that are outside 'boundary'
+    pub fn trim_snakes(&mut self, boundary: &ArenaRect) {
+        if let Some(snake) = &mut self.local_player().snake {
+            snake.trim(|pos| boundary.contains(pos));
+        }
+    }
+
+    /// Make every snake show, and our own snake go at the speed of, their player's 'power_ups'
+    pub fn apply_power_ups(&mut self, power_ups: &PowerUps, config: &PowerUpConfig, ui_cfg: &PowerUpUiConfig) {
+        for player in self.players.values_mut() {
+            if let Some(snake) = &mut player.snake {
+                power_ups.apply_to_snake(player.join_order, snake, config, ui_cfg);
+            }
+        }
+    }
+
+    /// Let our own snake grow as long as the coins we 'collected' allow, less the times
+    /// it was 'snatched', both by join order
+    pub fn apply_length_budgets(&mut self, collected: &HashMap<u32, u32>, snatched: &HashMap<u32, u32>) {
+        for player in self.players.values_mut() {
+            if let Some(snake) = &mut player.snake {
+                snake.set_coins_collected(collected.get(&player.join_order).copied().unwrap_or(0));
+                snake.set_times_snatched(snatched.get(&player.join_order).copied().unwrap_or(0));
+            }
+        }
+    }
+
+    /// Stop sending updates over 'handle', whose connection to the host was lost.
+    /// The players are kept as they were, in case we end up taking over as the host.
+    pub fn connection_lost(&mut self, handle: NetworkHandle) {
+        for (_, player) in self.players.iter_mut() {
+            if let Some(snake) = &mut player.snake {
+                snake.remove_peers_on(handle);
+            }
+        }
+    }
+
+    fn local_player(&mut self) -> &mut ClientPlayer {
+        self.players.values_mut().find(|p| p.is_local).unwrap()
+    }
+
+    fn local(&self) -> &ClientPlayer {
+        self.players.values().find(|p| p.is_local).unwrap()
+    }
+
+    /// Return true if we're only watching the game
+    pub fn is_spectating(&self) -> bool {
+        self.players.values().any(|p| p.is_local && p.is_spectator)
+    }
+
+    /// Return true if our own player has a snake
+    pub fn has_snake(&self) -> bool {
+        self.players.values().any(|p| p.is_local && p.snake.is_some())
+    }
+
+    /// Set our own player's join order, as decided by the host
+    pub fn set_join_order(&mut self, join_order: u32) {
+        self.local_player().join_order = join_order;
+    }
+
+    /// Return the state of every player we know about, as we see it
+    pub fn snapshot(&self) -> Vec<PlayerSnapshot> {
+        self.players.values()
+            .filter(|player| !player.is_spectator)
+            .map(|player| PlayerSnapshot {
+                name: player.name.clone(),
+                join_order: player.join_order,
+                snake_points: player.snake.as_ref().map(|snake| snake.points().clone()).unwrap_or_default(),
+                team: player.team,
+            })
+            .collect()
+    }
+
+    /// Set whether we're only watching the game, as decided by the host
+    pub fn set_spectating(&mut self, spectating: bool) {
+        self.local_player().is_spectator = spectating;
+    }
+
+    /// Handle the host's updated list of open 'snake_points'
+    pub fn update_start_points(&mut self, snake_points: &[Pos2d]) {
+        self.local_player().choices = snake_points.to_vec();
+    }
+
+    /// Ask the host to place our snake at 'pos' once placing is over
+    pub fn claim_start_point(&mut self, game: &mut dyn BaseGame, pos: Pos2d) {
+        let player = self.local_player();
+        if player.snake.is_none() && !player.is_spectator {
+            player.claim = Some(pos);
+            player.player_stream.send(game, PlayerMsg::RequestSnake(RequestSnakeMsg { pos }));
         }
     }
+
+    /// Return how every start point looks to our own player
+    pub fn start_point_markers(&self) -> Vec<(Pos2d, StartPointMarker)> {
+        let taken: Vec<Pos2d> = self.players.values()
+            .filter_map(|player| player.snake.as_ref().map(|snake| snake.get_start_pos()))
+            .collect();
+        let local = self.local();
+        start_point_markers(&local.choices, &taken, &self.claims, local.claim)
+    }
+
+    /// Ask the host to let us play, once the round is over
+    pub fn request_play(&mut self, game: &mut dyn BaseGame) {
+        self.local_player().player_stream.send(game, PlayerMsg::RequestPlay);
+    }
+
+    /// Return the join order of the client next in line to host, as told by the host
+    pub fn successor(&self) -> Option<u32> {
+        self.successor
+    }
+
+    /// Return the join orders of all the other (non-bot) players, including the host's
+    pub fn remote_join_orders(&self) -> Vec<u32> {
+        self.players.values().filter(|p| !p.is_local && !p.is_bot).map(|p| p.join_order).collect()
+    }
 }
@@ -0,0 +1,227 @@
+// Power-ups: pickups the host spawns in the arena during a round, giving whoever's head
+// reaches one an effect for a while.  The host keeps them in the match state, and sends
+// them to the clients whenever they change.
+
+use crate::painter::BackgroundConfig;
+use crate::players::PlayerSnapshot;
+use crate::snake::Snake;
+use crate::traits::BaseGame;
+
+use engine_p::interpolable::{Interpolable, Pos2d};
+use serde::{Serialize, Deserialize};
+use serde_with::serde_as;
+
+use std::collections::HashMap;
+
+/// Config types
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub enum PowerUpKind {
+    // Grows 'speed_boost_factor' times faster
+    SpeedBoost,
+
+    // Can't be snatched
+    Shield,
+
+    // Pulls in the coins within 'magnet_radius'
+    Magnet,
+
+    // Passes through other snakes: neither snatches nor gets snatched
+    Ghost,
+}
+
+// How likely a kind of power-up is to spawn, relative to the others, and how long its
+// effect lasts
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PowerUpSpawn {
+    pub kind: PowerUpKind,
+    pub weight: f64,
+    pub duration: f64, // seconds
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PowerUpConfig {
+    pub spawns: Vec<PowerUpSpawn>, // empty for no power-ups
+    pub spawn_interval: f64, // seconds between pickups spawning
+    pub max_pickups: usize, // in the arena at any time
+    pub pickup_radius: f64, // how close a snake's head must get to a pickup to take it
+    pub speed_boost_factor: f64,
+    pub magnet_radius: f64,
+    pub magnet_speed: f64,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct PowerUpUiConfig {
+    pub pickup: BackgroundConfig, // 'offset' is relative to the pickup, filled with the kind's style
+    pub speed_boost_style: String, // of the pickups, and the ring around the snake's head while it's active
+    pub shield_style: String,
+    pub magnet_style: String,
+    pub ghost_style: String,
+}
+
+impl PowerUpUiConfig {
+    fn style(&self, kind: PowerUpKind) -> &str {
+        match kind {
+            PowerUpKind::SpeedBoost => &self.speed_boost_style,
+            PowerUpKind::Shield => &self.shield_style,
+            PowerUpKind::Magnet => &self.magnet_style,
+            PowerUpKind::Ghost => &self.ghost_style,
+        }
+    }
+}
+
+/// PowerUps
+
+// A power-up waiting in the arena to be picked up
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Pickup {
+    pub kind: PowerUpKind,
+    pub pos: Pos2d,
+}
+
+// An effect a player picked up
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ActivePowerUp {
+    pub kind: PowerUpKind,
+    pub time_left: f64, // seconds, since our clocks differ
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct PowerUps {
+    pickups: Vec<Pickup>,
+    #[serde_as(as = "Vec<(_, _)>")]
+    active: HashMap<u32, Vec<ActivePowerUp>>, // by join order
+    spawn_time_left: f64, // seconds until the next pickup spawns
+}
+
+impl PowerUps {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start over, with a new round
+    pub fn reset(&mut self, config: &PowerUpConfig) {
+        self.pickups.clear();
+        self.active.clear();
+        self.spawn_time_left = config.spawn_interval;
+    }
+
+    /// Count down the effects' timers by 'elapsed' seconds, forgetting the ones that
+    /// ran out.  Return true if any did.
+    pub fn tick(&mut self, elapsed: f64) -> bool {
+        let mut expired = false;
+        for effects in self.active.values_mut() {
+            for effect in effects.iter_mut() {
+                effect.time_left -= elapsed;
+            }
+            let count = effects.len();
+            effects.retain(|effect| effect.time_left > 0.0);
+            expired |= effects.len() != count;
+        }
+        self.active.retain(|_, effects| !effects.is_empty());
+
+        expired
+    }
+
+    /// Apply the rules to the current state of the 'players', 'elapsed' seconds after
+    /// the last frame: effects run out, pickups spawn at positions from 'spawn_pos', and
+    /// whoever reaches one gets its effect.  Return true if anything changed.
+    pub fn think(&mut self, elapsed: f64, players: &[PlayerSnapshot], config: &PowerUpConfig,
+                 spawn_pos: impl Fn() -> Pos2d) -> bool {
+        let mut changed = self.tick(elapsed);
+
+        self.spawn_time_left -= elapsed;
+        if self.spawn_time_left <= 0.0 {
+            self.spawn_time_left = config.spawn_interval;
+            if self.pickups.len() < config.max_pickups {
+                if let Some(kind) = random_kind(config) {
+                    self.pickups.push(Pickup { kind, pos: spawn_pos() });
+                    changed = true;
+                }
+            }
+        }
+
+        for player in players.iter().filter(|p| !p.snake_points.is_empty()) {
+            let head = *player.snake_points.last().unwrap();
+            let (taken, left): (Vec<Pickup>, Vec<Pickup>) = self.pickups.iter()
+                .partition(|pickup| head.dist(pickup.pos) < config.pickup_radius);
+            if taken.is_empty() {
+                continue;
+            }
+
+            self.pickups = left;
+            let effects = self.active.entry(player.join_order).or_default();
+            for pickup in taken.into_iter() {
+                let duration = config.spawns.iter().find(|spawn| spawn.kind == pickup.kind).map_or(0.0, |spawn| spawn.duration);
+
+                // Picking up an effect we have starts it over
+                effects.retain(|effect| effect.kind != pickup.kind);
+                effects.push(ActivePowerUp { kind: pickup.kind, time_left: duration });
+            }
+            changed = true;
+        }
+
+        changed
+    }
+
+    /// Pull the 'coins' near the head of the 'players' with a magnet toward it, for
+    /// 'elapsed' seconds.  Return true if any moved.
+    pub fn attract(&self, coins: &mut [Pos2d], players: &[PlayerSnapshot], elapsed: f64, config: &PowerUpConfig) -> bool {
+        let mut moved = false;
+        for player in players.iter().filter(|p| !p.snake_points.is_empty() && self.has(p.join_order, PowerUpKind::Magnet)) {
+            let head = *player.snake_points.last().unwrap();
+            for coin in coins.iter_mut().filter(|coin| head.dist(**coin) < config.magnet_radius) {
+                let intr = Interpolable::new(*coin, config.magnet_speed);
+                intr.set_end(head);
+                intr.advance(elapsed);
+                *coin = intr.cur();
+                moved = true;
+            }
+        }
+
+        moved
+    }
+
+    /// Return true if the player with the specified 'join_order' has an effect of 'kind'
+    pub fn has(&self, join_order: u32, kind: PowerUpKind) -> bool {
+        self.active.get(&join_order).is_some_and(|effects| effects.iter().any(|effect| effect.kind == kind))
+    }
+
+    /// Make the 'snake' of the player with the specified 'join_order' show, and go at
+    /// the speed of, that player's effects
+    pub fn apply_to_snake(&self, join_order: u32, snake: &mut Snake, config: &PowerUpConfig, ui_cfg: &PowerUpUiConfig) {
+        let effects = self.active.get(&join_order).map(|effects| effects.as_slice()).unwrap_or(&[]);
+
+        let speed_factor = if self.has(join_order, PowerUpKind::SpeedBoost) { config.speed_boost_factor } else { 1.0 };
+        snake.set_speed_factor(speed_factor);
+        snake.set_indicators(effects.iter().map(|effect| ui_cfg.style(effect.kind).to_string()).collect());
+    }
+}
+
+/// Helper functions
+// Return a kind of power-up, picked at random according to the spawn weights
+fn random_kind(config: &PowerUpConfig) -> Option<PowerUpKind> {
+    let total: f64 = config.spawns.iter().map(|spawn| spawn.weight.max(0.0)).sum();
+    if total <= 0.0 {
+        return None;
+    }
+
+    let mut pick = js_sys::Math::random() * total;
+    for spawn in config.spawns.iter() {
+        pick -= spawn.weight.max(0.0);
+        if pick < 0.0 {
+            return Some(spawn.kind);
+        }
+    }
+    config.spawns.last().map(|spawn| spawn.kind)
+}
+
+/// Draw the pickups waiting in the arena
+pub fn draw_pickups(game: &dyn BaseGame, power_ups: &PowerUps, ui_cfg: &PowerUpUiConfig) {
+    for pickup in power_ups.pickups.iter() {
+        game.painter().draw_area_background(&pickup.pos, &BackgroundConfig {
+            bg_style: ui_cfg.style(pickup.kind).to_string(),
+            ..ui_cfg.pickup.clone()
+        });
+    }
+}
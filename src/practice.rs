@@ -0,0 +1,168 @@
+// Offline practice against the clock: collect coins as fast as possible, optionally
+// racing bots for them.  Nothing goes over the network, and personal best times are
+// kept in the browser's localStorage.
+
+use crate::painter::TextConfig;
+use crate::round::{draw_coins, random_coin, RoundConfig, RoundUiConfig};
+use crate::snake::{Snake, SnakeConfig, SnakeUiConfig};
+use crate::traits::BaseGame;
+use crate::utils::{load_local, store_local};
+use crate::widgets::{Button, ButtonConfig, ButtonThinkResult};
+
+use engine_p::interpolable::Pos2d;
+use serde::{Serialize, Deserialize};
+
+/// Config types
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PracticeConfig {
+    pub coin_target: u32, // coins to collect to finish a time trial
+    pub start_points: Vec<Pos2d>, // ours first, then one per bot
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct PracticeUiConfig {
+    pub info_text: TextConfig,
+    pub bots_button: ButtonConfig, // adds a bot, or goes back to none
+    pub restart_button: ButtonConfig,
+    pub exit_button: ButtonConfig,
+}
+
+/// PracticeManager
+pub struct PracticeManager {
+    snake: Snake,
+    bots: Vec<Snake>,
+    coins: Vec<Pos2d>,
+    collected: u32,
+    coin_target: u32,
+    start_time: f64,
+    finish_time: Option<f64>, // once all the coins are collected
+    best_time: Option<f64>, // for the current number of bots, from localStorage
+
+    bots_button: Button,
+    restart_button: Button,
+    exit_button: Button,
+}
+
+pub enum PracticeManagerThinkResult {
+    Exit,
+}
+
+// Return the localStorage key of the best time collecting 'coin_target' coins against 'bot_count' bots
+fn best_time_key(coin_target: u32, bot_count: usize) -> String {
+    format!("snake_snatch.practice.best.{}coins.{}bots", coin_target, bot_count)
+}
+
+impl PracticeManager {
+    pub fn new(game: &dyn BaseGame, config: &PracticeConfig, round_cfg: &RoundConfig) -> Self {
+        let mut mgr = PracticeManager {
+            snake: Snake::new_local("Practice", &(0,0).into()),
+            bots: Vec::new(),
+            coins: Vec::new(),
+            collected: 0,
+            coin_target: config.coin_target,
+            start_time: 0.0,
+            finish_time: None,
+            best_time: None,
+            bots_button: Button::new(),
+            restart_button: Button::new(),
+            exit_button: Button::new(),
+        };
+        mgr.restart(game, 0, config, round_cfg);
+        mgr
+    }
+
+    /// Start a new time trial against 'bot_count' bots
+    fn restart(&mut self, game: &dyn BaseGame, bot_count: usize, config: &PracticeConfig, round_cfg: &RoundConfig) {
+        let start = config.start_points.first().copied().unwrap_or_else(|| (0,0).into());
+        self.snake = Snake::new_local("Practice", &start);
+        self.bots = config.start_points.iter().skip(1).take(bot_count).enumerate()
+            .map(|(idx, pos)| Snake::new_bot(&format!("Bot {}", idx + 1), pos))
+            .collect();
+        self.coins = (0..round_cfg.coin_count).map(|_| random_coin(round_cfg)).collect();
+        self.collected = 0;
+        self.coin_target = config.coin_target;
+        self.start_time = game.now();
+        self.finish_time = None;
+        self.best_time = load_local(&best_time_key(config.coin_target, self.bots.len()))
+            .and_then(|time| time.parse().ok());
+    }
+
+    pub fn think(&mut self, game: &mut dyn BaseGame, config: &PracticeConfig, round_cfg: &RoundConfig,
+                 snake_cfg: &SnakeConfig, ui_cfg: &PracticeUiConfig) -> Option<PracticeManagerThinkResult> {
+        if self.exit_button.think(game.mouse(), &ui_cfg.exit_button).into_iter()
+            .any(|res| matches!(res, ButtonThinkResult::Clicked)) {
+            return Some(PracticeManagerThinkResult::Exit);
+        }
+
+        if self.restart_button.think(game.mouse(), &ui_cfg.restart_button).into_iter()
+            .any(|res| matches!(res, ButtonThinkResult::Clicked)) {
+            self.restart(game, self.bots.len(), config, round_cfg);
+        }
+
+        if self.bots_button.think(game.mouse(), &ui_cfg.bots_button).into_iter()
+            .any(|res| matches!(res, ButtonThinkResult::Clicked)) {
+            let max_bots = config.start_points.len().saturating_sub(1);
+            let bot_count = if self.bots.len() >= max_bots { 0 } else { self.bots.len() + 1 };
+            self.restart(game, bot_count, config, round_cfg);
+        }
+
+        self.snake.set_coins_collected(self.collected);
+        self.snake.think(game, snake_cfg, None);
+        for bot in self.bots.iter_mut() {
+            bot.set_bot_goals(self.coins.clone());
+            bot.think(game, snake_cfg, None);
+        }
+
+        if self.finish_time.is_some() {
+            return None;
+        }
+
+        // Bots take coins too, but only ours count
+        let head = self.snake.head();
+        for coin in self.coins.iter_mut() {
+            if head.dist(*coin) < round_cfg.coin_radius {
+                *coin = random_coin(round_cfg);
+                self.collected += 1;
+            }
+            else if self.bots.iter().any(|bot| bot.head().dist(*coin) < round_cfg.coin_radius) {
+                *coin = random_coin(round_cfg);
+            }
+        }
+
+        if self.collected >= self.coin_target {
+            let time = game.now() - self.start_time;
+            self.finish_time = Some(time);
+
+            if self.best_time.is_none_or(|best| time < best) {
+                self.best_time = Some(time);
+                store_local(&best_time_key(self.coin_target, self.bots.len()), &time.to_string());
+            }
+        }
+
+        None
+    }
+
+    pub fn draw(&self, game: &dyn BaseGame, round_ui_cfg: &RoundUiConfig, snake_ui_cfg: &SnakeUiConfig,
+                ui_cfg: &PracticeUiConfig) {
+        draw_coins(game, &self.coins, round_ui_cfg);
+        self.snake.draw(game, None);
+        self.snake.draw_meters(game, None, snake_ui_cfg);
+        for bot in self.bots.iter() {
+            bot.draw(game, None);
+        }
+
+        let time = self.finish_time.unwrap_or_else(|| game.now() - self.start_time);
+        let best = self.best_time.map_or("none".to_string(), |best| format!("{:.2}s", best));
+        let info = format!("{} / {} coins, {} bots, {:.2}s{}, best {}",
+                           self.collected.min(self.coin_target), self.coin_target, self.bots.len(), time,
+                           if self.finish_time.is_some() { " (done)" } else { "" }, best);
+
+        let painter = game.painter();
+        painter.draw_text(&info, &(0,0).into(), 1600.0, &ui_cfg.info_text);
+
+        let mouse = game.mouse();
+        self.bots_button.draw(mouse, painter, &ui_cfg.bots_button);
+        self.restart_button.draw(mouse, painter, &ui_cfg.restart_button);
+        self.exit_button.draw(mouse, painter, &ui_cfg.exit_button);
+    }
+}
@@ -0,0 +1,280 @@
+// Recording of matches, as the state of every snake at regular intervals, and a viewer
+// to play them back.  Replays are exported/imported as JSON by the page.
+
+use crate::game::{GameManagerConfig, GameManagerUiConfig};
+use crate::painter::{BackgroundConfig, TextConfig};
+use crate::players::PlayerSnapshot;
+use crate::round::draw_coins;
+use crate::snake::draw_snake_points;
+use crate::traits::{BaseGame, BUILD_ID};
+use crate::widgets::{Button, ButtonConfig, ButtonThinkResult};
+
+use engine_p::interpolable::Pos2d;
+use serde::{Serialize, Deserialize};
+use serde_with::serde_as;
+
+use std::collections::{HashMap, VecDeque};
+
+/// Config types
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReplayConfig {
+    pub record_interval: f64, // seconds between recorded frames
+    pub max_frames: usize, // kept while recording, the oldest ones are dropped beyond this
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ReplayUiConfig {
+    pub pause_button: ButtonConfig,
+    pub slower_button: ButtonConfig,
+    pub faster_button: ButtonConfig,
+    pub follow_button: ButtonConfig,
+    pub exit_button: ButtonConfig,
+    pub seek_bar: BackgroundConfig,
+    pub seek_done: BackgroundConfig, // drawn over 'seek_bar', scaled to the position
+    pub info_text: TextConfig,
+    pub follow_center: Pos2d, // where the followed snake's head is kept on screen
+    pub score_text: TextConfig, // for the first player, the next ones go 'score_line_height' lower
+    pub score_line_height: f64,
+}
+
+/// Replay types
+
+// The state of the match at 'time' seconds into the recording
+#[serde_as]
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ReplayFrame {
+    time: f64,
+    players: Vec<PlayerSnapshot>,
+    coins: Vec<Pos2d>,
+    #[serde_as(as = "Vec<(_, _)>")]
+    scores: HashMap<u32, u32>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Replay {
+    build_id: String,
+    config: GameManagerConfig,
+    frames: VecDeque<ReplayFrame>, // the first ones may have been dropped while recording
+}
+
+impl Replay {
+    /// Return the time of the first frame we have
+    fn start(&self) -> f64 {
+        self.frames.front().map_or(0.0, |frame| frame.time)
+    }
+
+    fn duration(&self) -> f64 {
+        self.frames.back().map_or(0.0, |frame| frame.time - self.start())
+    }
+
+    /// Return the last frame recorded at or before 'time' seconds after the first one, if any
+    fn frame_at(&self, time: f64) -> Option<&ReplayFrame> {
+        let time = time + self.start();
+        let idx = self.frames.partition_point(|frame| frame.time <= time);
+        self.frames.get(idx.max(1) - 1)
+    }
+}
+
+/// ReplayRecorder
+pub struct ReplayRecorder {
+    replay: Replay,
+    start_time: f64,
+    next_frame_time: f64,
+}
+
+impl ReplayRecorder {
+    pub fn new(config: &GameManagerConfig, now: f64) -> Self {
+        ReplayRecorder {
+            replay: Replay {
+                build_id: BUILD_ID.to_string(),
+                config: config.clone(),
+                frames: VecDeque::new(),
+            },
+            start_time: now,
+            next_frame_time: now,
+        }
+    }
+
+    /// Return true if it's time, at 'now', to record the next frame
+    pub fn wants_frame(&self, now: f64) -> bool {
+        now >= self.next_frame_time
+    }
+
+    /// Record a frame of the specified 'players', 'coins' and 'scores' at 'now',
+    /// dropping the oldest one if we have too many
+    pub fn record(&mut self, now: f64, config: &ReplayConfig, players: Vec<PlayerSnapshot>,
+                  coins: &[Pos2d], scores: &HashMap<u32, u32>) {
+        while self.replay.frames.len() >= config.max_frames.max(1) {
+            self.replay.frames.pop_front();
+        }
+        self.replay.frames.push_back(ReplayFrame {
+            time: now - self.start_time,
+            players,
+            coins: coins.to_vec(),
+            scores: scores.clone(),
+        });
+        self.next_frame_time = now + config.record_interval;
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+}
+
+/// ReplayViewer
+// Plays back a 'Replay', with pause, seek, speed control and a camera following a player
+pub struct ReplayViewer {
+    replay: Replay,
+    time: f64, // seconds into the replay
+    speed: f64,
+    paused: bool,
+    follow: Option<u32>, // join order of the player the camera follows
+    pub camera: Pos2d,
+
+    pause_button: Button,
+    slower_button: Button,
+    faster_button: Button,
+    follow_button: Button,
+    exit_button: Button,
+}
+
+pub enum ReplayViewerThinkResult {
+    Exit,
+}
+
+impl ReplayViewer {
+    pub fn new(replay: Replay) -> Self {
+        ReplayViewer {
+            replay,
+            time: 0.0,
+            speed: 1.0,
+            paused: false,
+            follow: None,
+            camera: (0,0).into(),
+            pause_button: Button::new(),
+            slower_button: Button::new(),
+            faster_button: Button::new(),
+            follow_button: Button::new(),
+            exit_button: Button::new(),
+        }
+    }
+
+    /// Follow the player after the one we're following, or nobody after the last one
+    fn follow_next(&mut self) {
+        let mut orders: Vec<u32> = match self.replay.frame_at(self.time) {
+            Some(frame) => frame.players.iter().map(|p| p.join_order).collect(),
+            None => Vec::new(),
+        };
+        orders.sort();
+
+        self.follow = match self.follow {
+            None => orders.first().copied(),
+            Some(cur) => orders.into_iter().find(|order| *order > cur),
+        };
+    }
+
+    pub fn think(&mut self, game: &dyn BaseGame, ui_cfg: &ReplayUiConfig) -> Option<ReplayViewerThinkResult> {
+        if self.exit_button.think(game.mouse(), &ui_cfg.exit_button).into_iter()
+            .any(|res| matches!(res, ButtonThinkResult::Clicked)) {
+            return Some(ReplayViewerThinkResult::Exit);
+        }
+
+        if self.pause_button.think(game.mouse(), &ui_cfg.pause_button).into_iter()
+            .any(|res| matches!(res, ButtonThinkResult::Clicked)) {
+            self.paused = !self.paused;
+        }
+
+        if self.slower_button.think(game.mouse(), &ui_cfg.slower_button).into_iter()
+            .any(|res| matches!(res, ButtonThinkResult::Clicked)) {
+            self.speed = (self.speed / 2.0).max(0.25);
+        }
+
+        if self.faster_button.think(game.mouse(), &ui_cfg.faster_button).into_iter()
+            .any(|res| matches!(res, ButtonThinkResult::Clicked)) {
+            self.speed = (self.speed * 2.0).min(8.0);
+        }
+
+        if self.follow_button.think(game.mouse(), &ui_cfg.follow_button).into_iter()
+            .any(|res| matches!(res, ButtonThinkResult::Clicked)) {
+            self.follow_next();
+        }
+
+        let duration = self.replay.duration();
+        let bar = &ui_cfg.seek_bar;
+        if game.mouse().is_down_in_rect(&bar.offset, bar.width, bar.height) {
+            // Seek to wherever the bar is held
+            let frac = (game.mouse().pos().x - bar.offset.x) / bar.width;
+            self.time = frac.clamp(0.0, 1.0) * duration;
+        }
+        else if !self.paused {
+            self.time = (self.time + game.elapsed_time() * self.speed).min(duration);
+        }
+
+        self.camera = (0,0).into();
+        if let (Some(order), Some(frame)) = (self.follow, self.replay.frame_at(self.time)) {
+            let followed = frame.players.iter().find(|p| p.join_order == order);
+            if let Some(head) = followed.and_then(|p| p.snake_points.last()) {
+                self.camera = (head.x - ui_cfg.follow_center.x, head.y - ui_cfg.follow_center.y).into();
+            }
+        }
+
+        None
+    }
+
+    /// Draw the frame at the current time: its coins and snakes, and the scores
+    pub fn draw(&self, game: &dyn BaseGame, game_ui_cfg: &GameManagerUiConfig) {
+        let ui_cfg = &game_ui_cfg.replay;
+        let frame = self.replay.frame_at(self.time);
+        let player_mgr = &self.replay.config.player_mgr;
+
+        if let Some(frame) = frame {
+            let canvas = game.painter().canvas();
+            canvas.save();
+            canvas.translate(-self.camera.x, -self.camera.y).expect("camera");
+            draw_coins(game, &frame.coins, &game_ui_cfg.round);
+            for player in frame.players.iter().filter(|p| !p.snake_points.is_empty()) {
+                draw_snake_points(game, &player.snake_points, player_mgr.snake_style(player.team),
+                                  self.replay.config.selected_map());
+            }
+            canvas.restore();
+
+            let mut players: Vec<&PlayerSnapshot> = frame.players.iter().collect();
+            players.sort_by_key(|p| p.join_order);
+            for (line, player) in players.into_iter().enumerate() {
+                let text = format!("{}: {}", &player.name, frame.scores.get(&player.join_order).copied().unwrap_or(0));
+                game.painter().draw_text(&text, &(0.0, line as f64 * ui_cfg.score_line_height).into(), 1000.0,
+                                         &TextConfig {
+                                             style: player_mgr.snake_style(player.team).to_string(),
+                                             ..ui_cfg.score_text.clone()
+                                         });
+            }
+        }
+
+        let duration = self.replay.duration();
+        let painter = game.painter();
+        painter.draw_area_background(&(0,0).into(), &ui_cfg.seek_bar);
+        if duration > 0.0 {
+            painter.draw_area_background(&(0,0).into(), &BackgroundConfig {
+                width: ui_cfg.seek_done.width * self.time / duration,
+                ..ui_cfg.seek_done.clone()
+            });
+        }
+
+        let following = match (self.follow, frame) {
+            (Some(order), Some(frame)) => frame.players.iter()
+                .find(|p| p.join_order == order)
+                .map_or("".to_string(), |p| format!(", following {}", &p.name)),
+            _ => "".to_string(),
+        };
+        let info = format!("Replay {:.1}s / {:.1}s, x{}{}{}", self.time, duration, self.speed,
+                           if self.paused { ", paused" } else { "" }, following);
+        painter.draw_text(&info, &(0,0).into(), 1600.0, &ui_cfg.info_text);
+
+        let mouse = game.mouse();
+        self.pause_button.draw(mouse, painter, &ui_cfg.pause_button);
+        self.slower_button.draw(mouse, painter, &ui_cfg.slower_button);
+        self.faster_button.draw(mouse, painter, &ui_cfg.faster_button);
+        self.follow_button.draw(mouse, painter, &ui_cfg.follow_button);
+        self.exit_button.draw(mouse, painter, &ui_cfg.exit_button);
+    }
+}
@@ -0,0 +1,509 @@
+// Rules of a round: coins to collect or zones to hold, other snakes to snatch, and who
+// wins.  Only the host applies them, the clients are told about the outcome.
+
+use crate::painter::{BackgroundConfig, TextConfig};
+use crate::players::{PlayerSnapshot, TeamConfig};
+use crate::powerups::{PowerUpKind, PowerUps};
+use crate::snake::SnakeConfig;
+use crate::traits::BaseGame;
+use crate::widgets::ButtonConfig;
+
+use engine_p::interpolable::Pos2d;
+use serde::{Serialize, Deserialize};
+use serde_with::serde_as;
+
+use std::collections::{HashMap, HashSet};
+
+/// Config types
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub enum GameMode {
+    // Snakes score by collecting coins
+    #[default]
+    Coins,
+
+    // Snakes score for every second their head stays in a zone.  The zones move
+    // every now and then.
+    KingOfTheHill,
+
+    // The arena shrinks, and the last snake left in it wins
+    ShrinkingArena,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub enum WinCondition {
+    // First to this score, or the best score once time runs out
+    ScoreTarget(u32),
+
+    // Best score once time runs out
+    TimeLimit,
+
+    // Last player (or team) with a snake with length left, or the longest snake still
+    // in once time runs out.  Each snatch takes 'length_per_snatch' off what the
+    // snatched snake may grow to, and a snake that may not grow at all is out.
+    LastSnake,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RoundConfig {
+    pub win_condition: WinCondition,
+    pub round_time: f64, // seconds a round lasts at most
+    pub coin_count: usize, // coins in the arena at any time
+    pub coin_radius: f64, // how close a snake's head must get to a coin to take it
+    pub snatch_radius: f64, // how close a snake's head must get to another snake to snatch it
+    pub coin_area_pos: Pos2d, // where coins and zones go
+    pub coin_area_width: f64,
+    pub coin_area_height: f64,
+    pub zone_count: usize,
+    pub zone_radius: f64,
+    pub zone_move_interval: f64, // seconds between the zones moving
+    pub zone_points_per_second: f64,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RoundUiConfig {
+    pub coin: BackgroundConfig, // 'offset' is relative to the coin
+    pub zone: BackgroundConfig, // scaled to each zone, with 'offset' and 'width' for a zone of radius 1
+    pub timer_text: TextConfig,
+    pub results_title: TextConfig,
+    pub results_text: TextConfig, // for the first player, the next ones go 'results_line_height' lower
+    pub results_line_height: f64,
+    pub rematch_button: ButtonConfig, // for the host, once the round is over
+}
+
+/// Results types
+
+// What a player did during a round
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct PlayerStats {
+    pub name: String,
+    pub max_length: f64,
+    pub coins: u32,
+    pub snatches: u32,
+    #[serde(default)]
+    pub team: Option<u32>,
+    #[serde(default)]
+    pub zone_time: f64, // seconds spent in a zone
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RoundResults {
+    pub winner: Option<u32>, // join order of the winner, or of the winning team's best player.  None on a draw
+    #[serde(default)]
+    pub winning_team: Option<u32>,
+    #[serde(default)]
+    pub team_names: Vec<String>,
+    #[serde_as(as = "Vec<(_, _)>")]
+    pub stats: HashMap<u32, PlayerStats>, // by join order
+}
+
+// What the host knows about a round beyond the coins and scores, replicated to the
+// clients so the next host can carry on with it
+#[serde_as]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct RoundState {
+    mode: GameMode,
+
+    // Number of players (or teams) with a snake when the round started
+    contenders: usize,
+
+    // Players whose snake ran out of length, or left a shrinking arena
+    eliminated: HashSet<u32>,
+
+    // Times each player's snake was snatched, by join order, where it costs the snake length
+    #[serde(default)]
+    #[serde_as(as = "Vec<(_, _)>")]
+    snatched: HashMap<u32, u32>,
+
+    // Where each snake started from, by join order, to tell when it's cut off from it
+    #[serde_as(as = "Vec<(_, _)>")]
+    starts: HashMap<u32, Pos2d>,
+}
+
+impl RoundState {
+    /// Return the number of times each player's snake was snatched so far this round,
+    /// where that costs it length, by join order
+    pub fn times_snatched(&self) -> &HashMap<u32, u32> {
+        &self.snatched
+    }
+}
+
+// A circle in the arena, to keep one's head in
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Zone {
+    pub pos: Pos2d, // center
+    pub radius: f64,
+}
+
+impl Zone {
+    fn contains(&self, pos: Pos2d) -> bool {
+        self.pos.dist(pos) < self.radius
+    }
+}
+
+// Who can win a round: a player on its own, or a team
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Side {
+    Player(u32), // by join order
+    Team(u32),
+}
+
+impl Side {
+    fn of(player: &PlayerSnapshot) -> Self {
+        match player.team {
+            Some(team) => Side::Team(team),
+            None => Side::Player(player.join_order),
+        }
+    }
+}
+
+/// Helper functions
+// Return the length of the snake made of 'points'
+fn snake_length(points: &[Pos2d]) -> f64 {
+    points.windows(2).map(|seg| seg[0].dist(seg[1])).sum()
+}
+
+// Return a random position for a coin
+pub fn random_coin(config: &RoundConfig) -> Pos2d {
+    (config.coin_area_pos.x + js_sys::Math::random() * config.coin_area_width,
+     config.coin_area_pos.y + js_sys::Math::random() * config.coin_area_height).into()
+}
+
+// Return a zone at a random position
+fn random_zone(config: &RoundConfig) -> Zone {
+    Zone {
+        pos: random_coin(config),
+        radius: config.zone_radius,
+    }
+}
+
+// Return the key of the single best of 'values', or None if several are tied for it
+fn unique_best<K: Copy>(values: impl Iterator<Item = (K, f64)>) -> Option<K> {
+    let values: Vec<(K, f64)> = values.collect();
+    let best = values.iter().map(|(_, value)| *value).fold(f64::NEG_INFINITY, f64::max);
+    let mut best_keys = values.iter().filter(|(_, value)| *value == best);
+
+    match (best_keys.next(), best_keys.next()) {
+        (Some((key, _)), None) => Some(*key),
+        _ => None,
+    }
+}
+
+// What a round's rules look at, and change, every frame
+pub struct RoundFrame<'a> {
+    pub players: &'a [PlayerSnapshot],
+    pub coins: &'a mut Vec<Pos2d>,
+    pub zones: &'a mut Vec<Zone>,
+    pub scores: &'a mut HashMap<u32, u32>, // by join order
+    pub power_ups: &'a PowerUps, // protecting the snakes from being snatched
+}
+
+/// RoundTracker
+// Keeps track, for the host, of what happens during a round
+pub struct RoundTracker {
+    stats: HashMap<u32, PlayerStats>, // by join order
+
+    // Pairs of (snatcher, snatched) join orders whose snakes touched in the last
+    // frame, so a snatch only counts once
+    touching: HashSet<(u32, u32)>,
+
+    state: RoundState,
+
+    // Points earned in a zone but not scored yet, as only whole points are, by join order
+    zone_points: HashMap<u32, f64>,
+
+    // Seconds until the zones move
+    zone_move_time: f64,
+}
+
+impl RoundTracker {
+    pub fn new() -> Self {
+        RoundTracker {
+            stats: HashMap::new(),
+            touching: HashSet::new(),
+            state: RoundState::default(),
+            zone_points: HashMap::new(),
+            zone_move_time: 0.0,
+        }
+    }
+
+    /// Carry on with the round described by 'state', after taking over as the host (or
+    /// restoring a saved game), between the specified 'players' who 'collected' coins
+    /// (by join order) so far
+    pub fn resume(state: &RoundState, players: &[PlayerSnapshot], collected: &HashMap<u32, u32>,
+                  config: &RoundConfig) -> Self {
+        RoundTracker {
+            stats: players.iter()
+                .filter(|p| !p.snake_points.is_empty())
+                .map(|p| (p.join_order, PlayerStats {
+                    name: p.name.clone(),
+                    team: p.team,
+                    coins: collected.get(&p.join_order).copied().unwrap_or(0),
+                    ..Default::default()
+                }))
+                .collect(),
+            touching: HashSet::new(),
+            state: state.clone(),
+            zone_points: HashMap::new(),
+            zone_move_time: config.zone_move_interval,
+        }
+    }
+
+    /// Return what the clients need to know to carry on with this round
+    pub fn state(&self) -> &RoundState {
+        &self.state
+    }
+
+    /// Start a round of the specified 'mode' with the specified 'players': put new
+    /// 'coins' or 'zones' in the arena, and clear the 'scores'
+    pub fn start(&mut self, players: &[PlayerSnapshot], coins: &mut Vec<Pos2d>, zones: &mut Vec<Zone>,
+                 scores: &mut HashMap<u32, u32>, mode: GameMode, config: &RoundConfig) {
+        let playing: Vec<&PlayerSnapshot> = players.iter().filter(|p| !p.snake_points.is_empty()).collect();
+
+        self.stats = playing.iter()
+            .map(|p| (p.join_order, PlayerStats { name: p.name.clone(), team: p.team, ..Default::default() }))
+            .collect();
+        self.touching.clear();
+        self.state = RoundState {
+            mode,
+            contenders: playing.iter().map(|p| Side::of(p)).collect::<HashSet<Side>>().len(),
+            eliminated: HashSet::new(),
+            snatched: HashMap::new(),
+            starts: playing.iter().map(|p| (p.join_order, p.snake_points[0])).collect(),
+        };
+        self.zone_points.clear();
+        self.zone_move_time = config.zone_move_interval;
+
+        coins.clear();
+        zones.clear();
+        match mode {
+            GameMode::Coins => *coins = (0..config.coin_count).map(|_| random_coin(config)).collect(),
+            GameMode::KingOfTheHill => *zones = (0..config.zone_count).map(|_| random_zone(config)).collect(),
+            GameMode::ShrinkingArena => {}
+        }
+        scores.clear();
+    }
+
+    /// Apply the rules to the current state of the 'frame', 'elapsed' seconds after the
+    /// last one: whoever reaches one of its coins takes it, whoever stays in one of its
+    /// zones scores, and whoever reaches another snake snatches it, taking one of its
+    /// points (and in a LastSnake round, some of the length 'snake_cfg' gives it),
+    /// unless they're teammates, or its power-ups protect it.  Return true if the coins,
+    /// zones, scores or the snatched snakes changed.
+    pub fn think(&mut self, elapsed: f64, frame: RoundFrame, config: &RoundConfig, snake_cfg: &SnakeConfig) -> bool {
+        let RoundFrame { players, coins, zones, scores, power_ups } = frame;
+        let mut changed = false;
+
+        if self.state.mode == GameMode::KingOfTheHill {
+            self.zone_move_time -= elapsed;
+            if self.zone_move_time <= 0.0 {
+                *zones = (0..config.zone_count).map(|_| random_zone(config)).collect();
+                self.zone_move_time = config.zone_move_interval;
+                changed = true;
+            }
+        }
+        let active: Vec<&PlayerSnapshot> = players.iter()
+            .filter(|p| !p.snake_points.is_empty() && !self.state.eliminated.contains(&p.join_order))
+            .collect();
+
+        for player in active.iter() {
+            let stats = self.stats.entry(player.join_order).or_default();
+            stats.name = player.name.clone();
+            stats.max_length = stats.max_length.max(snake_length(&player.snake_points));
+
+            let head = *player.snake_points.last().unwrap();
+            for coin in coins.iter_mut().filter(|coin| head.dist(**coin) < config.coin_radius) {
+                *coin = random_coin(config);
+                stats.coins += 1;
+                *scores.entry(player.join_order).or_insert(0) += 1;
+                changed = true;
+            }
+
+            if zones.iter().any(|zone| zone.contains(head)) {
+                stats.zone_time += elapsed;
+                let points = self.zone_points.entry(player.join_order).or_insert(0.0);
+                *points += elapsed * config.zone_points_per_second;
+                if *points >= 1.0 {
+                    *scores.entry(player.join_order).or_insert(0) += *points as u32;
+                    *points = points.fract();
+                    changed = true;
+                }
+            }
+        }
+
+        // A snake with no length left can't be snatched, and ghosts pass through the others
+        let ghost = |p: &PlayerSnapshot| power_ups.has(p.join_order, PowerUpKind::Ghost);
+        let shielded = |p: &PlayerSnapshot| ghost(p) || power_ups.has(p.join_order, PowerUpKind::Shield);
+        let mut touching = HashSet::new();
+        for snatcher in active.iter().filter(|p| !ghost(p)) {
+            let head = *snatcher.snake_points.last().unwrap();
+            for snatched in active.iter().filter(|p| Side::of(p) != Side::of(snatcher) && !shielded(p)) {
+                if snake_length(&snatched.snake_points) > 0.0 &&
+                   snatched.snake_points[1..].iter().any(|pos| head.dist(*pos) < config.snatch_radius) {
+                    touching.insert((snatcher.join_order, snatched.join_order));
+                }
+            }
+        }
+
+        let new_snatches: Vec<(u32, u32)> = touching.difference(&self.touching).copied().collect();
+        for (snatcher, snatched) in new_snatches.iter() {
+            self.stats.entry(*snatcher).or_default().snatches += 1;
+
+            if let Some(score) = scores.get_mut(snatched).filter(|score| **score > 0) {
+                *score -= 1;
+                *scores.entry(*snatcher).or_insert(0) += 1;
+            }
+
+            // A snake that may not grow at all anymore is out
+            if self.snatches_cost_length(config) {
+                let times = self.state.snatched.entry(*snatched).or_insert(0);
+                *times += 1;
+                let coins = self.stats.get(snatched).map_or(0, |stats| stats.coins);
+                if snake_cfg.length_budget(coins, *times) <= 0.0 {
+                    self.state.eliminated.insert(*snatched);
+                }
+            }
+            changed = true;
+        }
+        self.touching = touching;
+
+        changed
+    }
+
+    /// Return the number of coins each player collected so far this round, by join order
+    pub fn coins_collected(&self) -> HashMap<u32, u32> {
+        self.stats.iter().map(|(order, stats)| (*order, stats.coins)).collect()
+    }
+
+    /// Knock out the 'players' whose snake's head isn't 'inside' the arena anymore, or
+    /// whose snake was trimmed off its start and has no length left.  Return true if
+    /// anyone new was.
+    pub fn eliminate_outside(&mut self, players: &[PlayerSnapshot], inside: impl Fn(&Pos2d) -> bool) -> bool {
+        let mut changed = false;
+        for player in players.iter().filter(|p| !p.snake_points.is_empty()) {
+            let points = &player.snake_points;
+            let start = *self.state.starts.entry(player.join_order).or_insert(points[0]);
+            let trimmed_away = points[0] != start && snake_length(points) == 0.0;
+            if !inside(points.last().unwrap()) || trimmed_away {
+                changed |= self.state.eliminated.insert(player.join_order);
+            }
+        }
+        changed
+    }
+
+    /// Return how the round is won: the last snake left with length wins a shrinking arena
+    fn win_condition(&self, config: &RoundConfig) -> WinCondition {
+        match self.state.mode {
+            GameMode::ShrinkingArena => WinCondition::LastSnake,
+            _ => config.win_condition,
+        }
+    }
+
+    /// Return true if being snatched costs a snake length.  In a shrinking arena, only
+    /// the arena does.
+    fn snatches_cost_length(&self, config: &RoundConfig) -> bool {
+        self.state.mode != GameMode::ShrinkingArena && config.win_condition == WinCondition::LastSnake
+    }
+
+    /// Return the results if the round is over, given the current 'players' and
+    /// 'scores', and whether the round's time is up ('time_up').  Teammates' scores
+    /// add up.
+    pub fn results(&self, players: &[PlayerSnapshot], scores: &HashMap<u32, u32>, time_up: bool,
+                   config: &RoundConfig, teams: &[TeamConfig]) -> Option<RoundResults> {
+        let mut side_scores: HashMap<Side, u32> = HashMap::new();
+        for player in players.iter() {
+            *side_scores.entry(Side::of(player)).or_insert(0) += scores.get(&player.join_order).copied().unwrap_or(0);
+        }
+
+        let best_score = || unique_best(side_scores.iter().map(|(side, score)| (*side, *score as f64)));
+        let alive: Vec<&PlayerSnapshot> = players.iter()
+            .filter(|p| !p.snake_points.is_empty() && !self.state.eliminated.contains(&p.join_order))
+            .collect();
+        let alive_sides: HashSet<Side> = alive.iter().map(|p| Side::of(p)).collect();
+
+        let winner = match self.win_condition(config) {
+            WinCondition::ScoreTarget(target) => match side_scores.iter().find(|(_, score)| **score >= target) {
+                Some((side, _)) => Some(*side),
+                None if time_up => best_score(),
+                None => return None,
+            },
+            WinCondition::TimeLimit if time_up => best_score(),
+            WinCondition::LastSnake if alive_sides.len() <= 1 && self.state.contenders >= 2 => {
+                alive_sides.into_iter().next()
+            }
+            WinCondition::LastSnake if time_up => {
+                unique_best(alive.iter().map(|p| (Side::of(p), snake_length(&p.snake_points))))
+            }
+            _ => return None,
+        };
+
+        let (winner, winning_team) = match winner {
+            Some(Side::Player(order)) => (Some(order), None),
+            Some(Side::Team(team)) => {
+                let best = self.stats.iter()
+                    .filter(|(_, stats)| stats.team == Some(team))
+                    .max_by_key(|(order, stats)| (stats.coins, stats.snatches, std::cmp::Reverse(**order)))
+                    .map(|(order, _)| *order);
+                (best, Some(team))
+            }
+            None => (None, None),
+        };
+
+        Some(RoundResults {
+            winner,
+            winning_team,
+            team_names: teams.iter().map(|team| team.name.clone()).collect(),
+            stats: self.stats.clone(),
+        })
+    }
+}
+
+/// Drawing functions
+pub fn draw_coins(game: &dyn BaseGame, coins: &[Pos2d], ui_cfg: &RoundUiConfig) {
+    for coin in coins.iter() {
+        game.painter().draw_area_background(coin, &ui_cfg.coin);
+    }
+}
+
+pub fn draw_zones(game: &dyn BaseGame, zones: &[Zone], ui_cfg: &RoundUiConfig) {
+    for zone in zones.iter() {
+        let bg = &ui_cfg.zone;
+        game.painter().draw_area_background(&zone.pos, &BackgroundConfig {
+            offset: (bg.offset.x * zone.radius, bg.offset.y * zone.radius).into(),
+            width: bg.width * zone.radius,
+            height: bg.height * zone.radius,
+            corner_radius: bg.corner_radius * zone.radius,
+            ..bg.clone()
+        });
+    }
+}
+
+/// Draw the seconds left in the round, 'time_left'
+pub fn draw_timer(game: &dyn BaseGame, time_left: f64, ui_cfg: &RoundUiConfig) {
+    let text = format!("{}:{:02}", (time_left.ceil() as u32) / 60, (time_left.ceil() as u32) % 60);
+    game.painter().draw_text(&text, &(0,0).into(), 1600.0, &ui_cfg.timer_text);
+}
+
+/// Draw the winner of the round, and every player's stats
+pub fn draw_results(game: &dyn BaseGame, results: &RoundResults, ui_cfg: &RoundUiConfig) {
+    let painter = game.painter();
+
+    let team_name = |team: u32| results.team_names.get(team as usize).cloned().unwrap_or_else(|| format!("Team {}", team + 1));
+    let title = match (results.winning_team, results.winner.and_then(|order| results.stats.get(&order))) {
+        (Some(team), _) => format!("{} wins!", team_name(team)),
+        (None, Some(stats)) => format!("{} wins!", &stats.name),
+        (None, None) => "It's a draw".to_string(),
+    };
+    painter.draw_text(&title, &(0,0).into(), 1600.0, &ui_cfg.results_title);
+
+    let mut orders: Vec<&u32> = results.stats.keys().collect();
+    orders.sort();
+    for (line, order) in orders.into_iter().enumerate() {
+        let stats = &results.stats[order];
+        let team = stats.team.map_or("".to_string(), |team| format!(" ({})", team_name(team)));
+        let zone_time = if stats.zone_time > 0.0 { format!(", {:.0}s in zones", stats.zone_time) } else { "".to_string() };
+        let text = format!("{}{}: {} coins, {} snatches, longest {:.0}{}",
+                           &stats.name, team, stats.coins, stats.snatches, stats.max_length, zone_time);
+        painter.draw_text(&text, &(0.0, line as f64 * ui_cfg.results_line_height).into(), 1600.0, &ui_cfg.results_text);
+    }
+}
@@ -0,0 +1,365 @@
+// Series of rounds: best of N, or a single elimination bracket for more players than
+// fit in a round.  The host keeps it in the match state, so it outlives the host
+// leaving, or the game being saved.
+
+use crate::painter::TextConfig;
+use crate::round::RoundResults;
+use crate::traits::BaseGame;
+use crate::widgets::ButtonConfig;
+
+use serde::{Serialize, Deserialize};
+use serde_with::serde_as;
+
+use std::collections::HashMap;
+
+/// Config types
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub enum SeriesMode {
+    // Every round stands on its own
+    Single,
+
+    // First to win a majority of this many rounds
+    BestOf(u32),
+
+    // Single elimination, with 'bracket_match_size' players per match
+    Bracket,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SeriesConfig {
+    pub mode: SeriesMode,
+    pub bracket_match_size: usize,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SeriesUiConfig {
+    pub standings_title: TextConfig,
+    pub standings_text: TextConfig, // for the first line, the next ones go 'standings_line_height' lower
+    pub standings_line_height: f64,
+    pub next_round_button: ButtonConfig, // for the host, between the rounds of a series
+}
+
+/// Series types
+
+// Progress through a single elimination bracket.  Players are identified by join order.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Bracket {
+    stage: u32, // 1 for the first round of matches
+    current: Vec<u32>, // playing the current match
+    waiting: Vec<u32>, // yet to play in this stage
+    advanced: Vec<u32>, // won their match in this stage
+    eliminated: Vec<u32>,
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Series {
+    mode: SeriesMode,
+    rounds_played: u32,
+    #[serde_as(as = "Vec<(_, _)>")]
+    wins: HashMap<u32, u32>, // rounds won, by join order
+    #[serde_as(as = "Vec<(_, _)>")]
+    names: HashMap<u32, String>, // by join order
+    bracket: Option<Bracket>,
+    champion: Option<u32>,
+}
+
+impl Series {
+    /// Start a series between the specified 'entrants' (join order and name).  Bracket
+    /// matches are seeded in join order.
+    pub fn new(config: &SeriesConfig, mut entrants: Vec<(u32, String)>) -> Self {
+        entrants.sort_by_key(|(order, _)| *order);
+
+        let bracket = match config.mode {
+            SeriesMode::Bracket => Some(Bracket {
+                stage: 1,
+                current: Vec::new(),
+                waiting: entrants.iter().map(|(order, _)| *order).collect(),
+                advanced: Vec::new(),
+                eliminated: Vec::new(),
+            }),
+            _ => None,
+        };
+
+        let mut series = Series {
+            mode: config.mode,
+            rounds_played: 0,
+            wins: HashMap::new(),
+            names: entrants.into_iter().collect(),
+            bracket,
+            champion: None,
+        };
+        series.next_match(config);
+        series
+    }
+
+    pub fn mode(&self) -> SeriesMode {
+        self.mode
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.champion.is_some() || (self.mode == SeriesMode::Single && self.rounds_played > 0)
+    }
+
+    /// Return who plays the next round, if not everyone does
+    pub fn lineup(&self) -> Option<&Vec<u32>> {
+        self.bracket.as_ref().map(|bracket| &bracket.current)
+    }
+
+    /// Knock out the bracket's players that aren't 'present' anymore.  A player left
+    /// alone in its match goes through.
+    pub fn remove_absent(&mut self, present: &[u32], config: &SeriesConfig) {
+        let bracket = match &mut self.bracket {
+            Some(bracket) => bracket,
+            None => return,
+        };
+
+        for players in [&mut bracket.current, &mut bracket.waiting, &mut bracket.advanced] {
+            bracket.eliminated.extend(players.iter().filter(|order| !present.contains(order)));
+            players.retain(|order| present.contains(order));
+        }
+
+        if bracket.current.len() <= 1 {
+            bracket.advanced.append(&mut bracket.current);
+            self.next_match(config);
+        }
+    }
+
+    /// Record the outcome of a round, described by 'results'
+    pub fn record(&mut self, results: &RoundResults, config: &SeriesConfig) {
+        self.rounds_played += 1;
+        for (order, stats) in results.stats.iter() {
+            self.names.insert(*order, stats.name.clone());
+        }
+        // Every member of the winning team gets the win
+        let winners: Vec<u32> = match results.winning_team {
+            Some(team) => results.stats.iter()
+                .filter(|(_, stats)| stats.team == Some(team))
+                .map(|(order, _)| *order)
+                .collect(),
+            None => results.winner.into_iter().collect(),
+        };
+        for winner in winners.into_iter() {
+            *self.wins.entry(winner).or_insert(0) += 1;
+        }
+
+        match self.mode {
+            SeriesMode::Single => self.champion = results.winner,
+            SeriesMode::BestOf(rounds) => {
+                let needed = rounds / 2 + 1;
+                self.champion = self.wins.iter().find(|(_, wins)| **wins >= needed).map(|(order, _)| *order);
+            }
+            SeriesMode::Bracket => {
+                // A draw plays the match again
+                if let (Some(bracket), Some(winner)) = (&mut self.bracket, results.winner) {
+                    if bracket.current.contains(&winner) {
+                        bracket.eliminated.extend(bracket.current.iter().filter(|order| **order != winner));
+                        bracket.current.clear();
+                        bracket.advanced.push(winner);
+                        self.next_match(config);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Set up the bracket's next match, moving on to the next stage once everyone in
+    /// this one played.  A player with nobody left to play against goes through.
+    fn next_match(&mut self, config: &SeriesConfig) {
+        let bracket = match &mut self.bracket {
+            Some(bracket) => bracket,
+            None => return,
+        };
+
+        loop {
+            if bracket.waiting.is_empty() {
+                if bracket.advanced.len() <= 1 {
+                    self.champion = bracket.advanced.first().copied();
+                    return;
+                }
+
+                bracket.stage += 1;
+                bracket.waiting = std::mem::take(&mut bracket.advanced);
+            }
+
+            let size = config.bracket_match_size.max(2).min(bracket.waiting.len());
+            let players: Vec<u32> = bracket.waiting.drain(..size).collect();
+            if players.len() == 1 {
+                bracket.advanced.extend(players);
+                continue;
+            }
+
+            bracket.current = players;
+            return;
+        }
+    }
+
+    fn name(&self, order: u32) -> String {
+        self.names.get(&order).cloned().unwrap_or_else(|| format!("Player {}", order))
+    }
+
+    fn names(&self, orders: &[u32]) -> String {
+        orders.iter().map(|order| self.name(*order)).collect::<Vec<String>>().join(", ")
+    }
+}
+
+/// Draw where the series stands: the round wins so far, or the bracket
+pub fn draw_standings(game: &dyn BaseGame, series: &Series, ui_cfg: &SeriesUiConfig) {
+    let painter = game.painter();
+
+    let title = match (series.champion, series.mode, &series.bracket) {
+        (Some(order), _, _) => format!("{} wins the series!", series.name(order)),
+        (None, _, Some(bracket)) => format!("Bracket, stage {}", bracket.stage),
+        (None, SeriesMode::BestOf(rounds), _) => format!("Best of {}, round {} next", rounds, series.rounds_played + 1),
+        (None, _, _) => "".to_string(),
+    };
+    painter.draw_text(&title, &(0,0).into(), 1000.0, &ui_cfg.standings_title);
+
+    let lines: Vec<String> = match &series.bracket {
+        Some(bracket) => vec![
+            format!("Next match: {}", series.names(&bracket.current)),
+            format!("Waiting: {}", series.names(&bracket.waiting)),
+            format!("Through: {}", series.names(&bracket.advanced)),
+            format!("Out: {}", series.names(&bracket.eliminated)),
+        ],
+        None => {
+            let mut orders: Vec<&u32> = series.names.keys().collect();
+            orders.sort_by_key(|order| (std::cmp::Reverse(series.wins.get(order).copied().unwrap_or(0)), **order));
+            orders.into_iter()
+                .map(|order| format!("{}: {} wins", series.name(*order), series.wins.get(order).copied().unwrap_or(0)))
+                .collect()
+        }
+    };
+
+    for (line, text) in lines.iter().enumerate() {
+        painter.draw_text(text, &(0.0, line as f64 * ui_cfg.standings_line_height).into(), 1000.0, &ui_cfg.standings_text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::round::PlayerStats;
+
+    fn bracket_config(bracket_match_size: usize) -> SeriesConfig {
+        SeriesConfig { mode: SeriesMode::Bracket, bracket_match_size }
+    }
+
+    fn entrants(orders: &[u32]) -> Vec<(u32, String)> {
+        orders.iter().map(|order| (*order, format!("P{}", order))).collect()
+    }
+
+    // Results of a round between 'players', won by 'winner' (None on a draw)
+    fn results(players: &[u32], winner: Option<u32>) -> RoundResults {
+        RoundResults {
+            winner,
+            winning_team: None,
+            team_names: Vec::new(),
+            stats: players.iter()
+                .map(|order| (*order, PlayerStats { name: format!("P{}", order), ..Default::default() }))
+                .collect(),
+        }
+    }
+
+    fn bracket(series: &Series) -> &Bracket {
+        series.bracket.as_ref().unwrap()
+    }
+
+    #[test]
+    fn bracket_seeds_in_join_order() {
+        let config = bracket_config(2);
+        let series = Series::new(&config, entrants(&[4, 2, 3, 1]));
+
+        assert_eq!(series.lineup(), Some(&vec![1, 2]));
+        assert_eq!(bracket(&series).waiting, vec![3, 4]);
+        assert_eq!(bracket(&series).stage, 1);
+    }
+
+    #[test]
+    fn bracket_plays_through_to_a_champion() {
+        let config = bracket_config(2);
+        let mut series = Series::new(&config, entrants(&[1, 2, 3, 4]));
+
+        series.record(&results(&[1, 2], Some(2)), &config);
+        assert_eq!(series.lineup(), Some(&vec![3, 4]));
+
+        series.record(&results(&[3, 4], Some(3)), &config);
+        assert_eq!(bracket(&series).stage, 2);
+        assert_eq!(series.lineup(), Some(&vec![2, 3]));
+        assert!(!series.is_over());
+
+        series.record(&results(&[2, 3], Some(3)), &config);
+        assert!(series.is_over());
+        assert_eq!(series.champion, Some(3));
+        assert_eq!(bracket(&series).eliminated, vec![1, 4, 2]);
+    }
+
+    #[test]
+    fn bracket_gives_odd_player_a_bye() {
+        let config = bracket_config(2);
+        let mut series = Series::new(&config, entrants(&[1, 2, 3]));
+
+        // 3 has nobody to play in the first stage, and goes through
+        series.record(&results(&[1, 2], Some(1)), &config);
+        assert_eq!(bracket(&series).stage, 2);
+        assert_eq!(series.lineup(), Some(&vec![1, 3]));
+    }
+
+    #[test]
+    fn bracket_replays_a_draw() {
+        let config = bracket_config(2);
+        let mut series = Series::new(&config, entrants(&[1, 2, 3, 4]));
+
+        series.record(&results(&[1, 2], None), &config);
+        assert_eq!(series.lineup(), Some(&vec![1, 2]));
+        assert!(bracket(&series).eliminated.is_empty());
+
+        // Nor does a winner from outside the match count
+        series.record(&results(&[1, 2], Some(3)), &config);
+        assert_eq!(series.lineup(), Some(&vec![1, 2]));
+    }
+
+    #[test]
+    fn bracket_moves_on_when_a_player_leaves() {
+        let config = bracket_config(2);
+        let mut series = Series::new(&config, entrants(&[1, 2, 3, 4]));
+
+        // 1 left: 2 goes through without playing, and 3 and 4 play next
+        series.remove_absent(&[2, 3, 4], &config);
+        assert_eq!(series.lineup(), Some(&vec![3, 4]));
+        assert_eq!(bracket(&series).advanced, vec![2]);
+        assert_eq!(bracket(&series).eliminated, vec![1]);
+
+        // Someone waiting leaving doesn't change the current match
+        series.remove_absent(&[2, 3], &config);
+        assert_eq!(series.lineup(), Some(&vec![2, 3]));
+        assert_eq!(bracket(&series).stage, 2);
+    }
+
+    #[test]
+    fn bracket_with_bigger_matches() {
+        let config = bracket_config(3);
+        let mut series = Series::new(&config, entrants(&[1, 2, 3, 4, 5]));
+
+        assert_eq!(series.lineup(), Some(&vec![1, 2, 3]));
+        series.record(&results(&[1, 2, 3], Some(2)), &config);
+        assert_eq!(series.lineup(), Some(&vec![4, 5]));
+        series.record(&results(&[4, 5], Some(5)), &config);
+        assert_eq!(series.lineup(), Some(&vec![2, 5]));
+    }
+
+    #[test]
+    fn best_of_needs_a_majority() {
+        let config = SeriesConfig { mode: SeriesMode::BestOf(3), bracket_match_size: 2 };
+        let mut series = Series::new(&config, entrants(&[1, 2]));
+        assert_eq!(series.lineup(), None);
+
+        series.record(&results(&[1, 2], Some(1)), &config);
+        series.record(&results(&[1, 2], Some(2)), &config);
+        series.record(&results(&[1, 2], None), &config);
+        assert!(!series.is_over());
+
+        series.record(&results(&[1, 2], Some(2)), &config);
+        assert_eq!(series.champion, Some(2));
+    }
+}
@@ -0,0 +1,185 @@
+// Survival mode: the part of the arena the snakes can be in closes in, on a schedule
+// the host keeps, and the snake segments left outside are destroyed.  Every client
+// animates the boundary on its own, from where the host says it goes.
+
+use crate::painter::BackgroundConfig;
+use crate::traits::BaseGame;
+
+use engine_p::interpolable::{Interpolable, Pos2d};
+use serde::{Serialize, Deserialize};
+
+/// Config types
+
+// The arena closes in to 'scale' times its size (around its center), over 'duration'
+// seconds, starting 'start' seconds into the round
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ShrinkStage {
+    pub start: f64,
+    pub duration: f64,
+    pub scale: f64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ShrinkConfig {
+    pub stages: Vec<ShrinkStage>, // in order of 'start'
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ShrinkUiConfig {
+    pub boundary: BackgroundConfig, // drawn over the boundary, 'width' and 'height' are ignored
+}
+
+/// Network messages
+
+// Sent by the host to all clients when the boundary starts moving, and as part of
+// the snapshot
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BoundaryMsg {
+    pub from: ArenaRect,
+    pub to: ArenaRect,
+    pub time_left: f64, // seconds until it gets 'to'
+}
+
+/// ArenaRect
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ArenaRect {
+    pub pos: Pos2d, // top left corner
+    pub width: f64,
+    pub height: f64,
+}
+
+impl ArenaRect {
+    pub fn contains(&self, pos: &Pos2d) -> bool {
+        pos.x >= self.pos.x && pos.x <= self.pos.x + self.width &&
+        pos.y >= self.pos.y && pos.y <= self.pos.y + self.height
+    }
+
+    fn bottom_right(&self) -> Pos2d {
+        (self.pos.x + self.width, self.pos.y + self.height).into()
+    }
+
+    /// Return this rectangle, scaled by 'scale' around its center
+    fn scaled(&self, scale: f64) -> Self {
+        ArenaRect {
+            pos: (self.pos.x + self.width * (1.0 - scale) / 2.0, self.pos.y + self.height * (1.0 - scale) / 2.0).into(),
+            width: self.width * scale,
+            height: self.height * scale,
+        }
+    }
+}
+
+/// Boundary
+// The part of the arena the snakes can be in, moving toward where the host said
+pub struct Boundary {
+    top_left: Interpolable<Pos2d>,
+    bottom_right: Interpolable<Pos2d>,
+    target: ArenaRect,
+    time_left: f64,
+}
+
+impl Boundary {
+    pub fn new(rect: ArenaRect) -> Self {
+        Boundary {
+            top_left: Interpolable::new(rect.pos, 0.0),
+            bottom_right: Interpolable::new(rect.bottom_right(), 0.0),
+            target: rect,
+            time_left: 0.0,
+        }
+    }
+
+    /// Return a boundary moving as the specified 'msg' says
+    pub fn from_msg(msg: &BoundaryMsg) -> Self {
+        let mut boundary = Boundary::new(msg.from);
+        boundary.move_to(msg.to, msg.time_left);
+        boundary
+    }
+
+    /// Move to 'target' from where we are, over 'duration' seconds
+    fn move_to(&mut self, target: ArenaRect, duration: f64) {
+        let corner = |from: Pos2d, to: Pos2d| {
+            let intr = if duration > 0.0 {
+                Interpolable::new(from, from.dist(to) / duration)
+            }
+            else {
+                Interpolable::new(to, 0.0)
+            };
+            intr.set_end(to);
+            intr
+        };
+
+        let cur = self.rect();
+        self.top_left = corner(cur.pos, target.pos);
+        self.bottom_right = corner(cur.bottom_right(), target.bottom_right());
+        self.target = target;
+        self.time_left = duration;
+    }
+
+    pub fn think(&mut self, elapsed: f64) {
+        self.top_left.advance(elapsed);
+        self.bottom_right.advance(elapsed);
+        self.time_left = (self.time_left - elapsed).max(0.0);
+    }
+
+    /// Return where we are now
+    pub fn rect(&self) -> ArenaRect {
+        let top_left = self.top_left.cur();
+        let bottom_right = self.bottom_right.cur();
+        ArenaRect {
+            pos: top_left,
+            width: bottom_right.x - top_left.x,
+            height: bottom_right.y - top_left.y,
+        }
+    }
+
+    pub fn msg(&self) -> BoundaryMsg {
+        BoundaryMsg {
+            from: self.rect(),
+            to: self.target,
+            time_left: self.time_left,
+        }
+    }
+}
+
+/// ShrinkSchedule
+// Moves the boundary, for the host, as the stages of the config start
+pub struct ShrinkSchedule {
+    started: usize, // number of stages started
+}
+
+impl ShrinkSchedule {
+    pub fn new() -> Self {
+        ShrinkSchedule {
+            started: 0,
+        }
+    }
+
+    /// Start over, with a new round
+    pub fn reset(&mut self) {
+        self.started = 0;
+    }
+
+    /// Move the 'boundary' of the full 'arena' if a stage started by 'round_time'
+    /// seconds into the round.  Return true if it did.
+    pub fn think(&mut self, round_time: f64, boundary: &mut Boundary, arena: &ArenaRect, config: &ShrinkConfig) -> bool {
+        let started = config.stages.iter().filter(|stage| stage.start <= round_time).count();
+        if started <= self.started {
+            return false;
+        }
+
+        // Stages we missed (after taking over as the host) are applied right away
+        self.started = started;
+        let stage = &config.stages[started - 1];
+        boundary.move_to(arena.scaled(stage.scale), (stage.start + stage.duration - round_time).max(0.0));
+        true
+    }
+}
+
+/// Draw the 'boundary'
+pub fn draw_boundary(game: &dyn BaseGame, boundary: &Boundary, ui_cfg: &ShrinkUiConfig) {
+    let rect = boundary.rect();
+    game.painter().draw_area_background(&rect.pos, &BackgroundConfig {
+        width: rect.width,
+        height: rect.height,
+        ..ui_cfg.boundary.clone()
+    });
+}
@@ -3,14 +3,54 @@
 use engine_p::interpolable::{Interpolable, Pos2d};
 use serde::{Serialize,Deserialize};
 
-use crate::network::StreamHandle;
-use crate::traits::{BaseGame, NetMsg};
+use crate::input::InputSource;
+use crate::maps::MapConfig;
+use crate::network::{NetworkHandle, StreamHandle, TypedStream};
+use crate::painter::ProgressBarConfig;
+use crate::traits::BaseGame;
 use crate::utils::log;
 
 // Config structs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BotConfig {
+    pub reaction_time: f64, // seconds a bot waits before going after its next target
+    pub aim_error: f64, // how far off a target a bot may aim
+    pub speed_factor: f64, // bots grow at this fraction of 'grow_speed'
+    pub max_points: usize, // bots turn back once their snake has this many points
+}
+
+// A meter that drains while a snake grows, and refills while it doesn't: a snake
+// can't grow while it's empty
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StaminaConfig {
+    pub capacity: f64,
+    pub drain_rate: f64, // per second of growing
+    pub regen_rate: f64, // per second of shrinking back, or staying put
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SnakeConfig {
     pub grow_speed: f64,
+    pub start_length: f64, // length a snake may grow to before collecting any coins
+    pub length_per_coin: f64, // added to that length by each coin collected in the round
+    #[serde(default)]
+    pub length_per_snatch: f64, // taken off that length each time the snake is snatched, in a LastSnake round
+    pub stamina: Option<StaminaConfig>, // None for unlimited
+    pub bot: BotConfig,
+}
+
+impl SnakeConfig {
+    /// Return the length a snake that collected 'coins' coins, and was snatched
+    /// 'snatched' times, may grow to
+    pub fn length_budget(&self, coins: u32, snatched: u32) -> f64 {
+        (self.start_length + self.length_per_coin * coins as f64 - self.length_per_snatch * snatched as f64).max(0.0)
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SnakeUiConfig {
+    pub length_bar: ProgressBarConfig, // how much of its length budget a local snake uses, 'offset' being relative to its head
+    pub stamina_bar: ProgressBarConfig, // its stamina left, same as 'length_bar'
 }
 
 // Network Msgs
@@ -27,57 +67,120 @@ pub enum SnakeMsg {
     FullUpdateReq,
 }
 
-/// Helper Functions
-fn read_snake_msgs(updates: Vec<NetMsg>, stream: StreamHandle, cb: &mut dyn FnMut(SnakeMsg)) {
-    for upd in updates.into_iter() {
-        if let NetMsg::Snake(msg) = upd {
-            cb(msg);
-            continue;
-        }
-
-        log(&format!("Unexpected snake stream msg. Stream: {}, msg: {:?}", stream, &upd));
-    }
-}
+/// Helper types
+type SnakeStream = TypedStream<SnakeMsg>;
 
 /// SnakeData
 struct SnakeData {
     snake_points: Vec<Pos2d>,
     name: String,
     points_changed: bool,
+    speed_factor: f64, // multiplies how fast we grow, e.g. with a speed boost
+    coins_collected: u32, // this round, each adding to our length budget
+    times_snatched: u32, // this round, each taking from our length budget
+    length_budget: f64, // the length we may grow to, as of the last frame
+    stamina_used: f64,
+    stamina_capacity: Option<f64>, // as of the last frame, None if unlimited
 }
 
 impl SnakeData {
     fn points_sum(&self, num_points: usize) -> f64 {
         self.snake_points[0..num_points].iter().map(|p| p.x + p.y).sum::<f64>()
     }
-}
 
-/// OwnSnakeImp
-struct OwnSnakeImp {
-}
+    /// Return the length of the snake, not counting jumps through the portals of 'map'
+    fn length(&self, map: Option<&MapConfig>) -> f64 {
+        self.snake_points.windows(2)
+            .filter(|seg| !is_jump(map, seg[0], seg[1]))
+            .map(|seg| seg[0].dist(seg[1]))
+            .sum()
+    }
 
-impl OwnSnakeImp {
-    pub fn think(&mut self, data: &mut SnakeData, game: &dyn BaseGame, config: &SnakeConfig) {
-        // Update the size of our snake depending on if mouse is down or up
-        let snake_points = &mut data.snake_points;
-        let snake_intr = Interpolable::new(*snake_points.last().unwrap(), config.grow_speed);
-        if game.mouse().is_down() && game.mouse().pos() != *snake_points.last().unwrap() {
-            snake_intr.set_end(game.mouse().pos());
-            snake_intr.advance(game.elapsed_time());
-            *snake_points.last_mut().unwrap() = snake_intr.cur();
-
-            // Make sure each snake segment isn't too long
-            if snake_points.last().unwrap().dist(snake_points[snake_points.len()-2]) > 20.0 {
-                snake_points.push(*snake_points.last().unwrap());
+    /// Cut the snake back from its head to its length budget, not counting jumps
+    /// through the portals of 'map', e.g. after it was snatched
+    fn cut_to_budget(&mut self, map: Option<&MapConfig>) {
+        let mut excess = self.length(map) - self.length_budget;
+        while excess > 0.0 {
+            let points = &mut self.snake_points;
+            let (from, head) = (points[points.len()-2], *points.last().unwrap());
+            let segment = if is_jump(map, from, head) { 0.0 } else { from.dist(head) };
+            if segment > excess {
+                let t = excess / segment;
+                *points.last_mut().unwrap() = (head.x + (from.x - head.x) * t, head.y + (from.y - head.y) * t).into();
+                excess = 0.0;
             }
+            else if points.len() > 2 {
+                points.pop();
+                excess -= segment;
+            }
+            else {
+                *points.last_mut().unwrap() = from;
+                excess = 0.0;
+            }
+
+            self.points_changed = true;
+        }
+    }
 
-            data.points_changed = true;
+    /// Grow the snake toward 'target' at 'speed' if it's set, or shrink it back toward
+    /// its start otherwise, for 'elapsed_time' seconds, going around the obstacles of
+    /// 'map' and within the length budget and stamina of 'config'.  Return false if an
+    /// obstacle, or running out of length or stamina, stopped it.
+    fn steer(&mut self, target: Option<Pos2d>, speed: f64, elapsed_time: f64, map: Option<&MapConfig>,
+             config: &SnakeConfig) -> bool {
+        self.length_budget = config.length_budget(self.coins_collected, self.times_snatched);
+        self.cut_to_budget(map);
+        self.stamina_capacity = config.stamina.as_ref().map(|stamina| stamina.capacity);
+
+        let growing = target.is_some_and(|t| t != *self.snake_points.last().unwrap());
+        if let Some(stamina) = &config.stamina {
+            if !growing {
+                self.stamina_used = (self.stamina_used - stamina.regen_rate * elapsed_time).max(0.0);
+            }
+            else if self.stamina_used >= stamina.capacity {
+                return false;
+            }
         }
-        else if !game.mouse().is_down() && snake_points.len() > 2 {
-            // Shrink the snake while the mouse is up
+
+        let length = self.length(map);
+        let snake_points = &mut self.snake_points;
+        let head = *snake_points.last().unwrap();
+        let snake_intr = Interpolable::new(head, speed);
+        if let Some(target) = target.filter(|t| *t != head) {
+            snake_intr.set_end(target);
+            snake_intr.advance(elapsed_time);
+            let cur = snake_intr.cur();
+            if map.is_some_and(|map| map.blocks(head, cur)) || length + head.dist(cur) > self.length_budget {
+                return false;
+            }
+            *snake_points.last_mut().unwrap() = cur;
+            if let Some(stamina) = &config.stamina {
+                self.stamina_used = (self.stamina_used + stamina.drain_rate * elapsed_time).min(stamina.capacity);
+            }
+
+            if let Some(exit) = map.and_then(|map| map.portal_exit(cur)) {
+                // Jump to the portal's exit, and grow from there
+                snake_points.push(exit);
+                snake_points.push(exit);
+            }
+            else if cur.dist(snake_points[snake_points.len()-2]) > 20.0 {
+                // Make sure each snake segment isn't too long
+                snake_points.push(cur);
+            }
+
+            self.points_changed = true;
+        }
+        else if target.is_none() && snake_points.len() > 2 {
+            // Shrink the snake while the mouse is up, back through any portal at once
             let segment_start = snake_points[snake_points.len()-2];
+            if is_jump(map, segment_start, head) {
+                snake_points.pop();
+                self.points_changed = true;
+                return true;
+            }
+
             snake_intr.set_end(segment_start);
-            snake_intr.advance(game.elapsed_time());
+            snake_intr.advance(elapsed_time);
             let cur = snake_intr.cur();
             if cur == segment_start {
                 snake_points.pop();
@@ -86,7 +189,61 @@ impl OwnSnakeImp {
                 *snake_points.last_mut().unwrap() = cur;
             }
 
-            data.points_changed = true;
+            self.points_changed = true;
+        }
+
+        true
+    }
+}
+
+/// OwnSnakeImp
+struct OwnSnakeImp {
+    input: InputSource,
+}
+
+impl OwnSnakeImp {
+    pub fn think(&mut self, data: &mut SnakeData, game: &dyn BaseGame, config: &SnakeConfig, map: Option<&MapConfig>) {
+        // Update the size of our snake depending on if its input is active
+        let head = *data.snake_points.last().unwrap();
+        let target = game.input().target(&self.input, head, game.mouse());
+        data.steer(target, config.grow_speed * data.speed_factor, game.elapsed_time(), map, config);
+    }
+}
+
+/// BotSnakeImp
+// To drive a Snake by the host itself, going after the nearest of its 'goals'
+struct BotSnakeImp {
+    goals: Vec<Pos2d>,
+    target: Option<Pos2d>,
+    next_target_time: f64,
+}
+
+impl BotSnakeImp {
+    fn think(&mut self, data: &mut SnakeData, game: &dyn BaseGame, config: &SnakeConfig, map: Option<&MapConfig>) {
+        let head = *data.snake_points.last().unwrap();
+
+        if let Some(target) = self.target {
+            if head.dist(target) < 5.0 || data.snake_points.len() >= config.bot.max_points {
+                // Got there, or got too far: head back, and take a breather
+                self.target = None;
+                self.next_target_time = game.now() + config.bot.reaction_time;
+            }
+        }
+        else if data.snake_points.len() <= 2 && game.now() >= self.next_target_time {
+            let nearest = self.goals.iter()
+                .min_by(|a, b| head.dist(**a).total_cmp(&head.dist(**b)));
+
+            self.target = nearest.map(|goal| {
+                let error = || (js_sys::Math::random() * 2.0 - 1.0) * config.bot.aim_error;
+                (goal.x + error(), goal.y + error()).into()
+            });
+        }
+
+        let speed = config.grow_speed * config.bot.speed_factor * data.speed_factor;
+        if !data.steer(self.target, speed, game.elapsed_time(), map, config) {
+            // Something's in the way, or we're out of length or stamina: head back, and try again
+            self.target = None;
+            self.next_target_time = game.now() + config.bot.reaction_time;
         }
     }
 }
@@ -94,66 +251,61 @@ impl OwnSnakeImp {
 /// RemoteSnakeImp
 // To handle Snake events when it's controlled by a remote peer
 struct RemoteSnakeImp {
-    stream: StreamHandle,
+    stream: SnakeStream,
 }
 
 impl RemoteSnakeImp {
     fn think(&mut self, data: &mut SnakeData, game: &mut dyn BaseGame) {
-        let upds = game.network().get_stream_msgs(self.stream);
-        read_snake_msgs(upds, self.stream, &mut |msg| {
-            match &msg {
-                SnakeMsg::EndUpdate(upd) => {
-                    if data.snake_points.len() < upd.prev_segs {
-                        log(&format!("Snake({}) not enough segments for update: {:?}", data.name, msg));
-                        game.network().send(&self.stream, NetMsg::Snake(SnakeMsg::FullUpdateReq));
-                        return;
-                    }
-
-                    if data.points_sum(upd.prev_segs) != upd.prev_segs_sum {
-                        log(&format!("Snake({}) prev_segs_sum wrong for update: {:?}", data.name, msg));
-                        game.network().send(&self.stream, NetMsg::Snake(SnakeMsg::FullUpdateReq));
-                        return;
-                    }
-
-                    // Update can be processed fine
-                    let pts = &mut data.snake_points;
-                    pts.truncate(upd.prev_segs);
-                    upd.last_segs.iter().for_each(|p| pts.push(*p));
-
-                    data.points_changed = true;
-                },
-                _ => {
-                    log(&format!("Unexpected msg from snake remote: {} -> {:?}", self.stream, &msg));
+        let stream = self.stream;
+        stream.process_msgs(game, &mut |msg, g| match msg {
+            SnakeMsg::EndUpdate(upd) => {
+                if data.snake_points.len() < upd.prev_segs {
+                    log(&format!("Snake({}) not enough segments for update: {:?}", data.name, msg));
+                    stream.send(g, SnakeMsg::FullUpdateReq);
+                    g.network().count_full_update_req(&stream.stream());
+                    return true;
                 }
-            }
+
+                if data.points_sum(upd.prev_segs) != upd.prev_segs_sum {
+                    log(&format!("Snake({}) prev_segs_sum wrong for update: {:?}", data.name, msg));
+                    stream.send(g, SnakeMsg::FullUpdateReq);
+                    g.network().count_full_update_req(&stream.stream());
+                    return true;
+                }
+
+                // Update can be processed fine
+                let pts = &mut data.snake_points;
+                pts.truncate(upd.prev_segs);
+                upd.last_segs.iter().for_each(|p| pts.push(*p));
+
+                data.points_changed = true;
+                true
+            },
+            _ => false
         });
     }
 }
 
 /// SnakePeer
 struct SnakePeer {
-    stream: StreamHandle,
+    stream: SnakeStream,
     next_send_time: f64,
 }
 
 impl SnakePeer {
     fn think(&mut self, data: &mut SnakeData, game: &mut dyn BaseGame) {
-        let upds = game.network().get_stream_msgs(self.stream);
-        read_snake_msgs(upds, self.stream, &mut |msg| {
-            match &msg {
-                SnakeMsg::FullUpdateReq => {
-                    game.network().send(
-                        &self.stream,
-                        NetMsg::Snake(SnakeMsg::EndUpdate(EndUpdateMsg {
-                            prev_segs: 0,
-                            prev_segs_sum: 0.0,
-                            last_segs: data.snake_points.clone(),
-                        })));
-                },
-                _ => {
-                    log(&format!("Unexpected msg from snake peer: {} -> {:?}", self.stream, &msg));
-                }
-            }
+        let stream = self.stream;
+        stream.process_msgs(game, &mut |msg, g| match msg {
+            SnakeMsg::FullUpdateReq => {
+                g.network().count_full_update_req(&stream.stream());
+                stream.send(g, SnakeMsg::EndUpdate(EndUpdateMsg {
+                    prev_segs: 0,
+                    prev_segs_sum: 0.0,
+                    last_segs: data.snake_points.clone(),
+                }));
+                true
+            },
+            _ => false
         });
 
         if data.points_changed && self.next_send_time < game.now() {
@@ -161,22 +313,18 @@ impl SnakePeer {
             // be good enough and not require too many full updates
             let pts = &data.snake_points;
             if pts.len() > 2 {
-                game.network().send(
-                    &self.stream,
-                    NetMsg::Snake(SnakeMsg::EndUpdate(EndUpdateMsg {
-                        prev_segs: pts.len() - 2,
-                        prev_segs_sum: data.points_sum(pts.len() - 2),
-                        last_segs: pts[pts.len()-2..].iter().cloned().collect(),
-                    })));
+                self.stream.send(game, SnakeMsg::EndUpdate(EndUpdateMsg {
+                    prev_segs: pts.len() - 2,
+                    prev_segs_sum: data.points_sum(pts.len() - 2),
+                    last_segs: pts[pts.len()-2..].iter().cloned().collect(),
+                }));
             }
             else {
-                game.network().send(
-                    &self.stream,
-                    NetMsg::Snake(SnakeMsg::EndUpdate(EndUpdateMsg {
-                        prev_segs: 0,
-                        prev_segs_sum: 0.0,
-                        last_segs: pts.clone(),
-                    })));
+                self.stream.send(game, SnakeMsg::EndUpdate(EndUpdateMsg {
+                    prev_segs: 0,
+                    prev_segs_sum: 0.0,
+                    last_segs: pts.clone(),
+                }));
             }
 
             self.next_send_time = game.now() + 0.1; // At most one update every 100ms
@@ -187,8 +335,12 @@ impl SnakePeer {
 pub struct Snake {
     data: SnakeData,
     own_imp: Option<OwnSnakeImp>, // if this snake is controlled locally
+    bot_imp: Option<BotSnakeImp>, // if this snake is controlled by the host, as a bot
     remote_imp: Option<RemoteSnakeImp>, // if this snake is controlled remotely (by a peer)
     peers: Vec<SnakePeer>, // peers to send snake updates to
+    style: String, // stroke style it's drawn with
+    start_pos: Pos2d, // where it was placed, even once its first segments are destroyed
+    indicators: Vec<String>, // stroke styles of the rings drawn around its head, e.g. for power-ups
 }
 
 impl Snake {
@@ -198,11 +350,22 @@ impl Snake {
                 snake_points: vec![*start_pos, *start_pos],
                 name: name.to_string(),
                 points_changed: false,
+                speed_factor: 1.0,
+                coins_collected: 0,
+                times_snatched: 0,
+                length_budget: 0.0,
+                stamina_used: 0.0,
+                stamina_capacity: None,
             },
             own_imp: Some(OwnSnakeImp {
+                input: InputSource::Mouse,
             }),
+            bot_imp: None,
             remote_imp: None,
             peers: Vec::new(),
+            style: "black".to_string(),
+            start_pos: *start_pos,
+            indicators: Vec::new(),
         }
     }
 
@@ -212,33 +375,187 @@ impl Snake {
                 snake_points: vec![*start_pos, *start_pos],
                 name: name.to_string(),
                 points_changed: false,
+                speed_factor: 1.0,
+                coins_collected: 0,
+                times_snatched: 0,
+                length_budget: 0.0,
+                stamina_used: 0.0,
+                stamina_capacity: None,
             },
             own_imp: None,
+            bot_imp: None,
             remote_imp: Some(RemoteSnakeImp {
-                stream,
+                stream: SnakeStream::new(stream),
             }),
             peers: Vec::new(),
+            style: "black".to_string(),
+            start_pos: *start_pos,
+            indicators: Vec::new(),
         }
     }
 
+    /// Return a snake controlled by nobody, until it's given a remote stream
+    pub fn new_detached(name: &str, start_pos: &Pos2d) -> Self {
+        let mut snake = Self::new_local(name, start_pos);
+        snake.own_imp = None;
+        snake
+    }
+
+    pub fn new_bot(name: &str, start_pos: &Pos2d) -> Self {
+        let mut snake = Self::new_local(name, start_pos);
+        snake.make_bot();
+        snake
+    }
+
+    /// Take control of this snake as a bot, e.g. after its host left
+    pub fn make_bot(&mut self) {
+        self.own_imp = None;
+        self.remote_imp = None;
+        self.bot_imp = Some(BotSnakeImp {
+            goals: Vec::new(),
+            target: None,
+            next_target_time: 0.0,
+        });
+    }
+
+    /// Control this (local) snake with the specified 'input', instead of the mouse
+    pub fn set_input(&mut self, input: InputSource) {
+        if let Some(own) = &mut self.own_imp {
+            own.input = input;
+        }
+    }
+
+    /// Set the stroke style this snake is drawn with, e.g. its team's color
+    pub fn set_style(&mut self, style: &str) {
+        if self.style != style {
+            self.style = style.to_string();
+        }
+    }
+
+    /// Grow 'speed_factor' times as fast as configured
+    pub fn set_speed_factor(&mut self, speed_factor: f64) {
+        self.data.speed_factor = speed_factor;
+    }
+
+    /// Let this snake grow as long as a snake that collected 'coins' coins may
+    pub fn set_coins_collected(&mut self, coins: u32) {
+        self.data.coins_collected = coins;
+    }
+
+    /// Take the length of being snatched 'snatched' times off what this snake may grow to
+    pub fn set_times_snatched(&mut self, snatched: u32) {
+        self.data.times_snatched = snatched;
+    }
+
+    /// Draw a ring of each of the stroke 'styles' around our head
+    pub fn set_indicators(&mut self, styles: Vec<String>) {
+        if self.indicators != styles {
+            self.indicators = styles;
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.data.name
+    }
+
+    pub fn style(&self) -> &str {
+        &self.style
+    }
+
+    pub fn is_bot(&self) -> bool {
+        self.bot_imp.is_some()
+    }
+
+    /// Set the positions this (bot) snake goes after
+    pub fn set_bot_goals(&mut self, goals: Vec<Pos2d>) {
+        if let Some(bot) = &mut self.bot_imp {
+            bot.goals = goals;
+        }
+    }
+
+    // Return the position of the end of the snake
+    pub fn head(&self) -> Pos2d {
+        *self.data.snake_points.last().unwrap()
+    }
+
     pub fn add_peer(&mut self, stream: StreamHandle) {
         self.peers.push(SnakePeer {
-            stream,
+            stream: SnakeStream::new(stream),
             next_send_time: 0.0,
         });
     }
 
-    // Return our start_points (first 2 points of the snake)
+    /// Stop sending updates over the specified 'handle', whose connection is gone
+    pub fn remove_peers_on(&mut self, handle: NetworkHandle) {
+        self.peers.retain(|peer| peer.stream.stream().handle() != handle);
+    }
+
+    /// Close all the streams this snake uses, before it's dropped
+    pub fn close_streams(self, game: &mut dyn BaseGame) {
+        if let Some(remote) = &self.remote_imp {
+            game.network().close_stream(&remote.stream.stream());
+        }
+
+        for peer in self.peers.iter() {
+            game.network().close_stream(&peer.stream.stream());
+        }
+    }
+
+    /// Make this (remote) snake receive its updates over the specified 'stream'
+    pub fn set_remote_stream(&mut self, stream: StreamHandle) {
+        self.remote_imp = Some(RemoteSnakeImp {
+            stream: SnakeStream::new(stream),
+        });
+    }
+
+    pub fn points(&self) -> &Vec<Pos2d> {
+        &self.data.snake_points
+    }
+
+    /// Replace all our points by the specified 'points', which has at least 2 elements
+    pub fn set_points(&mut self, points: Vec<Pos2d>) {
+        self.data.snake_points = points;
+        self.data.points_changed = true;
+    }
+
+    // Return where we were placed
     pub fn get_start_pos(&self) -> Pos2d {
-        self.data.snake_points[0]
+        self.start_pos
+    }
+
+    /// Destroy our segments with a point that's not 'inside', if we control this
+    /// snake.  What's left is the part still attached to the head, if the head is
+    /// inside, or no length at all.
+    pub fn trim(&mut self, inside: impl Fn(&Pos2d) -> bool) {
+        if self.own_imp.is_none() && self.bot_imp.is_none() {
+            // Whoever controls it trims it, and tells us
+            return;
+        }
+
+        let pts = &self.data.snake_points;
+        let keep_from = match pts.iter().rposition(|pos| !inside(pos)) {
+            Some(idx) => idx + 1,
+            None => return,
+        };
+
+        let head = *pts.last().unwrap();
+        let rest = if pts.len() - keep_from >= 2 { pts[keep_from..].to_vec() } else { vec![head, head] };
+        if rest != *pts {
+            self.set_points(rest);
+        }
     }
 
-    // Handle per-frame processing
-    pub fn think(&mut self, game: &mut dyn BaseGame, config: &SnakeConfig) {
+    // Handle per-frame processing.  A locally controlled snake can't grow through the
+    // obstacles of 'map'.
+    pub fn think(&mut self, game: &mut dyn BaseGame, config: &SnakeConfig, map: Option<&MapConfig>) {
         self.data.points_changed = false;
 
         if let Some(own) = &mut self.own_imp {
-            own.think(&mut self.data, game, config);
+            own.think(&mut self.data, game, config, map);
+        }
+
+        if let Some(bot) = &mut self.bot_imp {
+            bot.think(&mut self.data, game, config, map);
         }
 
         if let Some(remote) = &mut self.remote_imp {
@@ -250,18 +567,57 @@ impl Snake {
         }
     }
 
-    // Draw our snake
-    pub fn draw(&self, game: &dyn BaseGame) {
+    // Draw our snake, and its indicators.  Jumps through the portals of 'map' aren't drawn.
+    pub fn draw(&self, game: &dyn BaseGame, map: Option<&MapConfig>) {
+        draw_snake_points(game, &self.data.snake_points, &self.style, map);
+
         let canvas = game.painter().canvas();
+        let head = self.head();
+        for (idx, style) in self.indicators.iter().enumerate() {
+            canvas.begin_path();
+            canvas.set_stroke_style_str(style);
+            canvas.set_line_width(4.0);
+            canvas.arc(head.x, head.y, 12.0 + idx as f64 * 6.0, 0.0, std::f64::consts::TAU).expect("indicator");
+            canvas.stroke();
+        }
+        canvas.begin_path();
+    }
+
+    /// Draw how much of its length budget, and of its stamina, our (local) snake has
+    /// left, in the arena of 'map'
+    pub fn draw_meters(&self, game: &dyn BaseGame, map: Option<&MapConfig>, ui_cfg: &SnakeUiConfig) {
+        if self.own_imp.is_none() || self.data.length_budget <= 0.0 {
+            return;
+        }
 
-        canvas.set_stroke_style_str("black");
-        canvas.set_line_width(10.0);
-        canvas.move_to(self.data.snake_points[0].x, self.data.snake_points[0].y);
-        for pos in self.data.snake_points[1..].iter() {
+        let painter = game.painter();
+        let head = self.head();
+        painter.draw_progress_bar(&head, (self.data.length(map) / self.data.length_budget).min(1.0), &ui_cfg.length_bar);
+        if let Some(capacity) = self.data.stamina_capacity.filter(|capacity| *capacity > 0.0) {
+            painter.draw_progress_bar(&head, 1.0 - self.data.stamina_used / capacity, &ui_cfg.stamina_bar);
+        }
+    }
+}
+
+// Return true if going from 'from' to 'to' is a jump through one of the portals of 'map'
+fn is_jump(map: Option<&MapConfig>, from: Pos2d, to: Pos2d) -> bool {
+    map.is_some_and(|map| map.is_jump(from, to))
+}
+
+/// Draw a snake made of the specified 'points', which has at least 1 element, with
+/// the stroke 'style', skipping its jumps through the portals of 'map'
+pub fn draw_snake_points(game: &dyn BaseGame, points: &[Pos2d], style: &str, map: Option<&MapConfig>) {
+    let canvas = game.painter().canvas();
+
+    canvas.set_stroke_style_str(style);
+    canvas.set_line_width(10.0);
+    canvas.move_to(points[0].x, points[0].y);
+    for (prev, pos) in points.iter().zip(points[1..].iter()) {
+        if !is_jump(map, *prev, *pos) {
             canvas.line_to(pos.x, pos.y);
             canvas.stroke();
-            canvas.begin_path();
-            canvas.move_to(pos.x, pos.y);
         }
+        canvas.begin_path();
+        canvas.move_to(pos.x, pos.y);
     }
 }
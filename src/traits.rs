@@ -3,17 +3,97 @@ use engine_p::interpolable::Pos2d;
 use serde::{Serialize, Deserialize};
 
 
+use crate::input::InputManager;
 use crate::mouse::MouseManager;
-use crate::network::NetworkManager;
+use crate::network::{NetworkManager, StreamHandle, StreamMsg};
 use crate::painter::Painter;
+use crate::game::GameMsg;
 use crate::players::{PlayerMsg, PlayersMsg};
 use crate::snake::SnakeMsg;
 
+// Version of the wire protocol.  Bump whenever a change to 'NetMsg' (or anything
+// it contains) would stop an older build from understanding a newer one.
+pub const PROTOCOL_VERSION: u32 = 22;
+
+// Identifies the build of the game, for diagnosing mismatched clients
+pub const BUILD_ID: &str = env!("CARGO_PKG_VERSION");
+
+// Optional features this build supports, advertised during the handshake
+pub const SUPPORTED_FEATURES: &[&str] = &[];
+
+// Features a client must support for the host to accept it
+pub const REQUIRED_FEATURES: &[&str] = &[];
+
 // Handshake sent by a client to the host, telling it the stream to use to send
-// player-relaed messages.  Sent over stream 0
+// player-relaed messages, and what the client is able to understand.  Sent over stream 0
+// The fields other than 'players_stream' default, so that a handshake from a build
+// older than the versioned protocol still parses, and can be rejected properly.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct NewClientMsg {
     pub players_stream: i32,
+    #[serde(default)]
+    pub protocol_version: u32,
+    #[serde(default)]
+    pub build_id: String,
+    #[serde(default)]
+    pub features: Vec<String>,
+    #[serde(default)]
+    pub spectate: bool, // to only watch the game
+    #[serde(default)]
+    pub session_token: Option<String>, // set when reconnecting, to resume our player
+    #[serde(default)]
+    pub join_order: Option<u32>, // set along with 'session_token'
+}
+
+impl NewClientMsg {
+    /// Return a handshake describing this build, using the specified 'players_stream',
+    /// spectating if 'spectate' is set, and resuming the session with the specified
+    /// 'session_token' and 'join_order', if any
+    pub fn new(players_stream: i32, spectate: bool, session_token: Option<String>, join_order: Option<u32>) -> Self {
+        Self {
+            players_stream,
+            protocol_version: PROTOCOL_VERSION,
+            build_id: BUILD_ID.to_string(),
+            features: SUPPORTED_FEATURES.iter().map(|f| f.to_string()).collect(),
+            spectate,
+            session_token,
+            join_order,
+        }
+    }
+
+    /// Return 'Err' with a user-readable reason if a client sending this handshake
+    /// can't play with us
+    pub fn check_compatible(&self) -> Result<(), String> {
+        if self.protocol_version != PROTOCOL_VERSION {
+            return Err(format!("Incompatible game version: host protocol {} (build {}), client protocol {} (build {})",
+                               PROTOCOL_VERSION, BUILD_ID, self.protocol_version, self.build_id));
+        }
+
+        let missing: Vec<&str> = REQUIRED_FEATURES.iter()
+            .filter(|f| !self.features.iter().any(|cf| cf == *f))
+            .cloned()
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!("Client is missing features: {}", missing.join(", ")));
+        }
+
+        Ok(())
+    }
+}
+
+// Details the host gives a client it accepted
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AcceptedMsg {
+    pub session_token: String, // to resume the session after reconnecting
+    pub join_order: u32, // decides who takes over if the host leaves
+    pub spectator: bool, // set if we're only watching, as asked or because the game is full
+}
+
+// Sent by the host over stream 0 in reply to a 'NewClientMsg'
+#[derive(Debug, Deserialize, Serialize)]
+pub enum NewClientReplyMsg {
+    Accepted(AcceptedMsg),
+    Rejected(String), // reason the client can't join
 }
 
 
@@ -26,6 +106,12 @@ pub enum NetMsg {
     // Sent by a new client to the host
     NewClient(NewClientMsg),
 
+    // Sent by the host to a new client, accepting or rejecting it
+    NewClientReply(NewClientReplyMsg),
+
+    // GameManager-specific messages, sent by the host over stream 0
+    Game(GameMsg),
+
     // PlayerManager-specific messages
     Players(PlayersMsg),
 
@@ -41,6 +127,40 @@ pub enum NetMsg {
     StartPointsUpdate(Vec<Pos2d>),
 }
 
+// Implement 'StreamMsg' for the sub-message type 'msg', carried by 'NetMsg::variant'
+macro_rules! impl_stream_msg {
+    ($msg:ty, $variant:ident) => {
+        impl StreamMsg for $msg {
+            fn into_net_msg(self) -> NetMsg {
+                NetMsg::$variant(self)
+            }
+
+            fn from_net_msg(msg: NetMsg) -> Result<Self, NetMsg> {
+                match msg {
+                    NetMsg::$variant(inner) => Ok(inner),
+                    other => Err(other),
+                }
+            }
+        }
+    };
+}
+
+impl_stream_msg!(PlayersMsg, Players);
+impl_stream_msg!(PlayerMsg, Player);
+impl_stream_msg!(SnakeMsg, Snake);
+
+/// Handle a 'NetMsg::Ping' with the specified 'time', received over 'stream': answer
+/// requests, and record the round-trip time of responses
+pub fn handle_ping(game: &mut dyn BaseGame, stream: &StreamHandle, time: f64) {
+    if time > 0.0 {
+        game.network().send(stream, NetMsg::Ping(-time));
+    }
+    else {
+        let rtt = game.now() + time;
+        game.network().record_rtt(stream.handle(), rtt);
+    }
+}
+
 pub trait BaseGame {
     //fn set_global_alpha(&self, alpha: f64);
 
@@ -59,4 +179,6 @@ pub trait BaseGame {
     fn now(&self) -> f64;
 
     fn mouse(&self) -> &MouseManager;
+
+    fn input(&self) -> &InputManager;
 }
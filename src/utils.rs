@@ -1,4 +1,5 @@
 
+use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
 pub fn set_panic_hook() {
@@ -17,3 +18,22 @@ extern "C" {
     #[wasm_bindgen(js_namespace = console)]
     pub fn log(s: &str);
 }
+
+/// Return 'value' as a plain JS value that survives 'JSON.stringify', e.g. to save as a file
+pub fn to_json_value<T: Serialize>(value: &T) -> JsValue {
+    value.serialize(&serde_wasm_bindgen::Serializer::json_compatible()).unwrap()
+}
+
+/// Return the value stored under 'key' in the browser's localStorage, if any
+pub fn load_local(key: &str) -> Option<String> {
+    web_sys::window()?.local_storage().ok()??.get_item(key).ok()?
+}
+
+/// Store 'value' under 'key' in the browser's localStorage, if there's one
+pub fn store_local(key: &str, value: &str) {
+    if let Some(Ok(Some(storage))) = web_sys::window().map(|window| window.local_storage()) {
+        if storage.set_item(key, value).is_err() {
+            log(&format!("Failed to store {}", key));
+        }
+    }
+}
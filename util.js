@@ -1,7 +1,8 @@
 // Helper JS shared between index.html and dev.html
 
 
-import init, { run_frame, init_state, default_config, update_config } from './pkg/snake_snatch.js';
+import init, { run_frame, init_state, default_config, update_config, export_replay, load_replay,
+         export_game_state, import_game_state } from './pkg/snake_snatch.js';
 
 export function default_cfg() {
   return default_config();
@@ -11,6 +12,40 @@ export function update_cfg(new_config) {
   return update_config(new_config);
 }
 
+// Save the specified 'value' as a JSON file, named starting with 'prefix'
+function download_json(value, prefix) {
+  if (value === undefined) {
+    return;
+  }
+
+  let blob = new Blob([JSON.stringify(value)], {type: "application/json"});
+  let link = document.createElement("a");
+  link.href = URL.createObjectURL(blob);
+  link.download = `${prefix}_${Date.now()}.json`;
+  link.click();
+  URL.revokeObjectURL(link.href);
+}
+
+// Save the match recorded by the game with the specified 'state' index as a file
+export function download_replay(state) {
+  download_json(export_replay(state), "snake_snatch_replay");
+}
+
+// Play back the replay in the specified 'file' in the game with the specified 'state' index
+export async function open_replay(state, file) {
+  return load_replay(state, JSON.parse(await file.text()));
+}
+
+// Save everything the game with the specified 'state' index is hosting as a file
+export function download_game_state(state) {
+  download_json(export_game_state(state), "snake_snatch_state");
+}
+
+// Host the game saved in the specified 'file' in the game with the specified 'state' index
+export async function open_game_state(state, file) {
+  return import_game_state(state, JSON.parse(await file.text()));
+}
+
 // List out all resources to be loaded here
 const resources = {images: [], sounds: []};
 
@@ -55,12 +90,15 @@ export async function load_resources() {
 
 let run_loop_started = false;
 
+// Start a game on the specified 'canvas', and return its state index
 export function init_game(canvas) {
   let gameConfig = {...default_config(), ...{ }};
-  init_state(gameConfig, canvas, images, audioCtx, sounds);
+  let state = init_state(gameConfig, canvas, images, audioCtx, sounds);
 
   if (!run_loop_started) {
     run_loop_started = true;
     setInterval(function() { run_frame(); }, 1000/30);
   }
+
+  return state;
 }